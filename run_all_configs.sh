@@ -7,8 +7,14 @@ rm -rf "$OUTPUT_DIR/*"
 mkdir -p "$OUTPUT_DIR"
 
 # 20 seeds
-SEEDS=(3 1234 1337 4242 5678 9001 9876 13579 24680 31415 78910 \
-        101112 111213 112358 123456 161803 202122 271828 445566 778899)
+SEEDS=3,1234,1337,4242,5678,9001,9876,13579,24680,31415,78910,101112,111213,112358,123456,161803,202122,271828,445566,778899
+
+# config1 - nothing
+# config2 - position
+# config3 - position + goal
+# config4 - position + goal + map
+# config5 - all
+CONFIGS=none,positions,positions+goal,positions+goal+maze,all
 
 declare -a configs=(
     "2 8"
@@ -19,38 +25,6 @@ declare -a configs=(
 for cfg in "${configs[@]}"; do
    read -r AGENTS MAP <<< "$cfg"
 
-   DIR="a${AGENTS}_m${MAP}"
-   mkdir -p "$OUTPUT_DIR/$DIR"
-
-   for SEED in "${SEEDS[@]}"; do
-        #config 1 - nothing
-        $EXEC --headless -n "$AGENTS" -m "$MAP" --simulation-speed x128 -s "$SEED" \
-        --disable-position-sharing \
-        --disable-goal-sharing \
-        --disable-map-sharing \
-        --disable-agent-guiding \
-        -o "$OUTPUT_DIR/$DIR/config1_seed${SEED}.json"
-
-        #config 2 - position
-        $EXEC --headless -n "$AGENTS" -m "$MAP" --simulation-speed x128 -s "$SEED" \
-        --disable-goal-sharing \
-        --disable-map-sharing \
-        --disable-agent-guiding \
-        -o "$OUTPUT_DIR/$DIR/config2_seed${SEED}.json"
-
-        #config 3 - position + goal
-        $EXEC --headless -n "$AGENTS" -m "$MAP" --simulation-speed x128 -s "$SEED" \
-        --disable-map-sharing \
-        --disable-agent-guiding \
-        -o "$OUTPUT_DIR/$DIR/config3_seed${SEED}.json"
-
-        #config 4 - position + goal + map
-        $EXEC --headless -n "$AGENTS" -m "$MAP" --simulation-speed x128 -s "$SEED" \
-        --disable-agent-guiding \
-        -o "$OUTPUT_DIR/$DIR/config4_seed${SEED}.json"
-
-        #config 5 - all
-        $EXEC --headless -n "$AGENTS" -m "$MAP" --simulation-speed x128 -s "$SEED" \
-        -o "$OUTPUT_DIR/$DIR/config5_seed${SEED}.json"
-   done
-done
\ No newline at end of file
+   $EXEC batch -n "$AGENTS" -m "$MAP" -s "$SEEDS" -c "$CONFIGS" -j "$(nproc)" \
+   -o "$OUTPUT_DIR/a${AGENTS}_m${MAP}.json"
+done
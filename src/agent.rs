@@ -1,34 +1,51 @@
 use std::collections::HashSet;
 
 use bevy::prelude::*;
+use rand::{SeedableRng, rngs::StdRng};
 
 use crate::{
     GameState,
-    maze::{self, Maze, Tile},
+    maze::{self, Maze},
+    replay::Replay,
     rng::GlobalRng,
-    settings::SimulationSettings,
-    simulation::{SimulationStepData, SimulationStepEvent},
-    utils::{heuristics, node_utils},
+    settings::{Mission, Role, SimulationSettings, Strategy},
+    simulation::{self, SimulationStepData, SimulationStepEvent},
+    strategy::{self, AgentStrategy, FrontierContext},
+    utils::{
+        allocation,
+        grid::TileSet,
+        heuristics,
+        node_utils::{self, Observation},
+        spawn_utils,
+    },
 };
-use rand::seq::SliceRandom;
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Clone)]
 pub struct SharedMazeKnowledge {
-    pub exit_pos: Option<(usize, usize)>,
+    pub known_goals: Vec<(usize, usize)>,
+    /// Goals visited by any agent, the others only know about them when sharing the goal
+    pub visited_goals: HashSet<(usize, usize)>,
     pub agent_positions: Vec<(usize, usize)>,
-    pub explored_tiles: HashSet<(usize, usize)>,
+    pub explored_tiles: TileSet,
     pub remaining_agents: u32,
 }
 
-#[derive(Component, Default)]
+#[derive(Component, Default, Clone)]
 pub struct Agent {
     pub id: usize,
-    pub explored: HashSet<(usize, usize)>,
+    pub explored: TileSet,
+    /// Every tile the agent sensed, walls and tiles it can not walk to yet included
+    pub seen: TileSet,
     pub frontier: Vec<(usize, usize)>,
     pub position: (usize, usize),
+    pub previous_position: (usize, usize),
     pub current_path: Option<Vec<(usize, usize)>>,
     pub found_goal: Option<(usize, usize)>,
     pub current_goal: (usize, usize),
+    pub visited_goals: HashSet<(usize, usize)>,
+    pub finished_at: Option<u64>,
+    pub role: Role,
+    pub strategy: Strategy,
 }
 
 #[derive(Event)]
@@ -43,7 +60,9 @@ impl Plugin for AgentPlugin {
         app.add_systems(
             OnEnter(GameState::Simulation),
             (
-                (spawn_agents).after(maze::create_maze),
+                (spawn_agents)
+                    .after(maze::create_maze)
+                    .run_if(not(resource_exists::<Replay>)),
                 setup_shared_knowledge.after(spawn_agents),
             ),
         );
@@ -51,23 +70,28 @@ impl Plugin for AgentPlugin {
         app.add_systems(
             FixedUpdate,
             (
-                agent_explore_node,
+                agent_explore_node.after(simulation::tick_simulation),
                 agent_check_path.after(agent_explore_node),
                 agent_backtrack_path.after(agent_check_path),
                 agent_check_frontier.after(agent_backtrack_path),
-                move_agent_path.after(agent_check_frontier),
+                agent_allocate_frontier.after(agent_check_frontier),
+                move_agent_path.after(agent_allocate_frontier),
             )
-                .run_if(on_event::<SimulationStepEvent>),
+                .run_if(on_event::<SimulationStepEvent>)
+                .run_if(not(resource_exists::<Replay>)),
         );
     }
 }
 
 impl Agent {
-    fn new(id: usize, position: (usize, usize), goal: (usize, usize)) -> Self {
+    pub fn new(id: usize, position: (usize, usize), maze: &Maze) -> Self {
         Self {
             id,
             position,
-            current_goal: goal,
+            previous_position: position,
+            explored: TileSet::new(maze.width(), maze.height()),
+            seen: TileSet::new(maze.width(), maze.height()),
+            current_goal: maze.goals[0],
             ..default()
         }
     }
@@ -78,38 +102,90 @@ impl Agent {
             None => false,
         }
     }
-}
 
-//---------------------- funcs ------------------------------------------------
+    /// Whether the goal still needs a visit, as far as the agent knows.
+    fn needs_goal(
+        &self,
+        goal: (usize, usize),
+        knowledge: &SharedMazeKnowledge,
+        settings: &SimulationSettings,
+    ) -> bool {
+        let shared_visit = settings.share_goal && knowledge.visited_goals.contains(&goal);
+        !self.visited_goals.contains(&goal) && !shared_visit
+    }
 
-fn get_valid_map_positions(grid: &Vec<Vec<Tile>>) -> Vec<(usize, usize)> {
-    grid.iter()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.iter()
-                .enumerate()
-                .map(|(x, tile)| ((x, y), *tile))
-                .collect::<Vec<((usize, usize), Tile)>>()
-        })
-        .filter(|(_, tile)| *tile == Tile::Floor)
-        .map(|((x, y), _)| (x, y))
-        .collect::<Vec<(usize, usize)>>()
+    /// The goals the agent knows about, every goal of the maze unless they are unknown.
+    fn known_goals(
+        &self,
+        knowledge: &SharedMazeKnowledge,
+        maze: &Maze,
+        settings: &SimulationSettings,
+    ) -> Vec<(usize, usize)> {
+        if !settings.unknown_goals {
+            return maze.goals.clone();
+        }
+        let mut goals = knowledge.known_goals.clone();
+        let shared_visits = knowledge
+            .visited_goals
+            .iter()
+            .filter(|_| settings.share_goal);
+        self.found_goal
+            .iter()
+            .chain(&self.visited_goals)
+            .chain(shared_visits)
+            .for_each(|goal| {
+                if !goals.contains(goal) {
+                    goals.push(*goal);
+                }
+            });
+        goals
+    }
+
+    /// Whether the agent looks for new tiles, followers only move once they know a goal.
+    fn explores(&self, knowledge: &SharedMazeKnowledge, settings: &SimulationSettings) -> bool {
+        self.role != Role::Follower || self.target_goal(knowledge, settings).is_some()
+    }
+
+    /// The goal to head to, the one found by the agent itself before the shared ones.
+    fn target_goal(
+        &self,
+        knowledge: &SharedMazeKnowledge,
+        settings: &SimulationSettings,
+    ) -> Option<(usize, usize)> {
+        self.found_goal
+            .filter(|goal| self.needs_goal(*goal, knowledge, settings))
+            .or_else(|| {
+                knowledge
+                    .known_goals
+                    .iter()
+                    .find(|goal| self.needs_goal(**goal, knowledge, settings))
+                    .copied()
+            })
+    }
 }
 
+//---------------------- funcs ------------------------------------------------
+
 pub fn spawn_agents(
     mut commands: Commands,
     maze: Res<Maze>,
-    mut rand: ResMut<GlobalRng>,
+    rand: Res<GlobalRng>,
     settings: Res<SimulationSettings>,
 ) {
-    let mut possible_positions = get_valid_map_positions(&maze.tile_grid);
-    possible_positions.shuffle(&mut rand.rng);
-    possible_positions
+    let floor = maze.floor_positions();
+    let mut rng = StdRng::seed_from_u64(rand.spawn_seed);
+    spawn_utils::spawn_positions(&maze, floor, &settings, &mut rng)
         .iter()
-        .take(settings.num_agents as usize)
         .enumerate()
         .for_each(|(id, (x, y))| {
-            commands.spawn(Agent::new(id, (*x, *y), maze.goal));
+            let mut agent = Agent::new(id, (*x, *y), &maze);
+            (agent.role, agent.strategy) = settings.team_member(id);
+            if settings.unknown_goals {
+                // not heading to any goal until one is found
+                agent.current_goal = agent.position;
+            }
+            let strategy = AgentStrategy(strategy::create_strategy(agent.strategy));
+            commands.spawn((agent, strategy));
         });
 }
 
@@ -119,15 +195,18 @@ fn despawn_agents(mut commands: Commands, query: Query<Entity, With<Agent>>) {
         .for_each(|entity| commands.entity(entity).despawn());
 }
 
-fn setup_shared_knowledge(
+pub fn setup_shared_knowledge(
     query: Query<&Agent>,
     mut knowledge: ResMut<SharedMazeKnowledge>,
+    maze: Res<Maze>,
     settings: Res<SimulationSettings>,
 ) {
-    knowledge.remaining_agents = settings.num_agents;
-    knowledge.exit_pos = None;
+    // a maze with fewer floor tiles than agents spawns fewer of them
+    knowledge.remaining_agents = query.iter().count() as u32;
+    knowledge.known_goals = Vec::new();
+    knowledge.visited_goals = HashSet::new();
     knowledge.agent_positions = Vec::with_capacity(settings.num_agents as usize);
-    knowledge.explored_tiles = HashSet::new();
+    knowledge.explored_tiles = TileSet::new(maze.width(), maze.height());
     query.iter().for_each(|agent| {
         let (x, y) = agent.position;
         if settings.share_positions {
@@ -144,22 +223,54 @@ fn agent_explore_node(
 ) {
     query
         .iter_mut()
-        .filter(|a| a.position != maze.goal)
+        .filter(|a| a.finished_at.is_none())
         .filter(|a| !a.has_path())
         .for_each(|mut agent| {
             let current_position = agent.position;
-            let children =
-                node_utils::explore_node(agent.position, &maze, &agent.explored, &agent.frontier);
-            if let Some(found_goal) = children.iter().find(|pos| **pos == maze.goal) {
+            let Observation {
+                explored: observed,
+                frontier: children,
+                seen,
+            } = node_utils::explore_node(
+                agent.position,
+                &maze,
+                &settings.sensor,
+                &agent.explored,
+                &agent.frontier,
+            );
+            // a goal is found once in view, even when no known path leads to it yet
+            let found_goals = seen
+                .iter()
+                .filter(|pos| maze.is_goal(**pos) && !agent.seen.contains(pos))
+                .filter(|pos| agent.needs_goal(**pos, &knowledge, &settings))
+                .copied()
+                .collect::<Vec<(usize, usize)>>();
+            seen.iter().for_each(|n| {
+                agent.seen.insert(*n);
+            });
+            if let Some(found_goal) = found_goals.first() {
                 agent.found_goal = Some(*found_goal);
-                if settings.share_goal {
-                    knowledge.exit_pos = Some(*found_goal);
-                }
+            }
+            if settings.share_goal {
+                found_goals.iter().for_each(|goal| {
+                    if !knowledge.known_goals.contains(goal) {
+                        knowledge.known_goals.push(*goal);
+                    }
+                });
             }
             agent.explored.insert(current_position);
+            observed.iter().for_each(|n| {
+                agent.explored.insert(*n);
+            });
+            if !observed.is_empty() {
+                agent.frontier.retain(|n| !observed.contains(n));
+            }
             agent.frontier.extend(children);
             if settings.share_tiles {
                 knowledge.explored_tiles.insert(current_position);
+                observed.iter().for_each(|n| {
+                    knowledge.explored_tiles.insert(*n);
+                });
             }
         });
 }
@@ -168,36 +279,44 @@ fn agent_check_path(
     mut query: Query<&mut Agent>,
     knowledge: Res<SharedMazeKnowledge>,
     maze: Res<Maze>,
+    settings: Res<SimulationSettings>,
 ) {
     query
         .iter_mut()
-        .filter(|a| a.position != maze.goal)
-        .filter(|a| a.current_goal == maze.goal)
-        .filter(|a| a.found_goal.is_some() || knowledge.exit_pos.is_some())
+        .filter(|a| a.finished_at.is_none())
+        // lost agents can not be guiding others
+        .filter(|a| {
+            a.found_goal.is_none()
+                || a.known_goals(&knowledge, &maze, &settings)
+                    .contains(&a.current_goal)
+        })
         .for_each(|mut agent| {
-            let union_explored = agent
-                .explored
-                .union(&knowledge.explored_tiles)
-                .map(|n| *n)
-                .collect();
-            if let Some(path) = node_utils::find_know_path_to_node(
-                agent.position,
-                maze.goal,
-                &maze,
-                &union_explored,
-            ) {
+            let Some(goal) = agent.target_goal(&knowledge, &settings) else {
+                // the goal it was heading to got visited by another agent
+                if agent.found_goal.take().is_some() {
+                    agent.current_path = None;
+                }
+                return;
+            };
+            agent.current_goal = goal;
+            let union_explored = agent.explored.union(&knowledge.explored_tiles);
+            if let Some(path) =
+                node_utils::find_know_path_to_node(agent.position, goal, &maze, &union_explored)
+            {
                 agent.current_path = Some(path);
-                agent.found_goal = Some(maze.goal);
+                agent.found_goal = Some(goal);
             }
         });
 }
 
 fn agent_backtrack_path(
     mut query: Query<&mut Agent>,
+    knowledge: Res<SharedMazeKnowledge>,
     settings: Res<SimulationSettings>,
     maze: Res<Maze>,
 ) {
-    if settings.share_positions && settings.enable_guiding {
+    // lost agents only need help to find an exit
+    if settings.mission == Mission::AnyExit && settings.share_positions && settings.enable_guiding {
         let mut lost_agents = query
             .iter()
             .filter(|a| a.found_goal.is_none())
@@ -209,7 +328,8 @@ fn agent_backtrack_path(
                 .iter_mut()
                 .filter(|a| a.found_goal.is_some())
                 .for_each(|mut agent| {
-                    if agent.current_goal == maze.goal {
+                    let goals = agent.known_goals(&knowledge, &maze, &settings);
+                    if goals.contains(&agent.current_goal) {
                         agent.current_path = None;
                     }
                     agent.current_goal = helping;
@@ -217,40 +337,51 @@ fn agent_backtrack_path(
         } else {
             query
                 .iter_mut()
-                .for_each(|mut a| a.current_goal = maze.goal);
+                .for_each(|mut a| a.current_goal = a.found_goal.unwrap_or(a.current_goal));
         }
     }
 }
 
 fn agent_check_frontier(
-    mut query: Query<&mut Agent>,
+    mut query: Query<(&mut Agent, &AgentStrategy)>,
     knowledge: Res<SharedMazeKnowledge>,
     maze: Res<Maze>,
+    settings: Res<SimulationSettings>,
+    mut rand: ResMut<GlobalRng>,
 ) {
     query
         .iter_mut()
-        .filter(|a| a.position != maze.goal)
-        .filter(|a| !a.has_path())
-        .for_each(|mut agent| {
-            if agent.found_goal.is_none() && knowledge.exit_pos.is_none() {
-                let current_position = agent.position;
-                agent.frontier.sort_by(|n1, n2| {
-                    heuristics::goal_cmp(*n1, *n2, maze.goal)
-                        .then_with(|| heuristics::explored_cmp(*n1, *n2, &knowledge.explored_tiles))
-                        .then_with(|| heuristics::manhattan_cmp(*n1, *n2, current_position))
-                        .then_with(|| heuristics::border_cmp(*n1, *n2, maze.tile_grid.len()))
-                        .then_with(|| {
-                            heuristics::neighbors_cmp(*n1, *n2, &knowledge.agent_positions)
-                        })
-                });
+        .filter(|(a, _)| a.finished_at.is_none())
+        .filter(|(a, _)| !a.has_path())
+        .for_each(|(mut agent, strategy)| {
+            if agent.target_goal(&knowledge, &settings).is_none() {
+                let goals = agent
+                    .known_goals(&knowledge, &maze, &settings)
+                    .into_iter()
+                    .filter(|goal| agent.needs_goal(*goal, &knowledge, &settings))
+                    .collect::<Vec<(usize, usize)>>();
+                let context = FrontierContext {
+                    position: agent.position,
+                    previous_position: agent.previous_position,
+                    goals: &goals,
+                    explored: &knowledge.explored_tiles,
+                    agent_positions: &knowledge.agent_positions,
+                    sensor_radius: settings.sensor.radius,
+                    width: maze.width(),
+                    height: maze.height(),
+                };
+                strategy
+                    .0
+                    .order_frontier(&mut agent.frontier, &context, &mut rand.rng);
             } else {
                 let current_goal = agent.current_goal;
+                let goals = agent.known_goals(&knowledge, &maze, &settings);
                 agent.frontier.sort_by(|n1, n2| {
                     let ord = heuristics::goal_cmp(*n1, *n2, current_goal)
                         .then_with(|| heuristics::explored_cmp(*n1, *n2, &knowledge.explored_tiles))
                         .then_with(|| heuristics::manhattan_cmp(*n1, *n2, current_goal));
-                    if current_goal != maze.goal {
-                        heuristics::goal_cmp(*n2, *n1, maze.goal).then(ord)
+                    if !goals.contains(&current_goal) {
+                        heuristics::goals_cmp(*n2, *n1, &goals).then(ord)
                     } else {
                         ord
                     }
@@ -259,7 +390,90 @@ fn agent_check_frontier(
         });
 }
 
-fn move_agent_path(
+/// The best frontier tiles of every exploring agent, by its strategy, that get allocated.
+const FRONTIER_PROPOSALS: usize = 8;
+
+fn agent_allocate_frontier(
+    mut query: Query<&mut Agent>,
+    knowledge: Res<SharedMazeKnowledge>,
+    maze: Res<Maze>,
+    settings: Res<SimulationSettings>,
+) {
+    if !settings.coordinated || !settings.share_tiles {
+        return;
+    }
+    // the tiles agents are already heading to stay theirs
+    let mut pursued = query
+        .iter()
+        .filter_map(|a| a.current_path.as_ref().and_then(|path| path.first()))
+        .copied()
+        .collect::<HashSet<(usize, usize)>>();
+    let mut exploring = query
+        .iter_mut()
+        .filter(|a| a.finished_at.is_none())
+        .filter(|a| !a.has_path())
+        .filter(|a| a.role == Role::Scout && a.target_goal(&knowledge, &settings).is_none())
+        .collect::<Vec<Mut<Agent>>>();
+    exploring.sort_by_key(|a| a.id);
+    let mut targets = Vec::new();
+    exploring.iter().for_each(|agent| {
+        agent
+            .frontier
+            .iter()
+            .rev()
+            .filter(|n| !knowledge.explored_tiles.contains(n) && !pursued.contains(n))
+            .take(FRONTIER_PROPOSALS)
+            .for_each(|n| {
+                if !targets.contains(n) {
+                    targets.push(*n);
+                }
+            });
+    });
+    let union_explored = exploring
+        .iter()
+        .map(|agent| agent.explored.union(&knowledge.explored_tiles))
+        .collect::<Vec<TileSet>>();
+    let costs = exploring
+        .iter()
+        .zip(&union_explored)
+        .map(|(agent, explored)| {
+            let lengths = node_utils::known_path_lengths(agent.position, &maze, explored);
+            targets
+                .iter()
+                .map(|target| lengths.get(target).copied())
+                .collect::<Vec<Option<usize>>>()
+        })
+        .collect::<Vec<Vec<Option<usize>>>>();
+    let assignments = allocation::assign_targets(&costs, targets.len());
+    let mut unassigned = Vec::new();
+    exploring
+        .into_iter()
+        .zip(union_explored)
+        .zip(assignments)
+        .for_each(|((agent, explored), assignment)| match assignment {
+            Some(target) => {
+                let target = targets[target];
+                follow_path_to(agent, target, &maze, &explored);
+                pursued.insert(target);
+            }
+            None => unassigned.push((agent, explored)),
+        });
+    // the others take their best own tile that no other agent is heading to
+    unassigned.into_iter().for_each(|(agent, explored)| {
+        if let Some(target) = agent.frontier.iter().rev().find(|n| !pursued.contains(n)) {
+            let target = *target;
+            follow_path_to(agent, target, &maze, &explored);
+            pursued.insert(target);
+        }
+    });
+}
+
+fn follow_path_to(mut agent: Mut<Agent>, target: (usize, usize), maze: &Maze, explored: &TileSet) {
+    agent.frontier.retain(|n| *n != target);
+    agent.current_path = node_utils::find_know_path_to_node(agent.position, target, maze, explored);
+}
+
+pub fn move_agent_path(
     mut query: Query<&mut Agent>,
     maze: Res<Maze>,
     mut knowledge: ResMut<SharedMazeKnowledge>,
@@ -267,33 +481,48 @@ fn move_agent_path(
     mut step_data: ResMut<SimulationStepData>,
     mut events: EventWriter<AgentsCompleteMazeEvent>,
 ) {
+    let mut all_goals_visited = false;
     query
         .iter_mut()
-        .filter(|a| a.position != maze.goal)
+        .filter(|a| a.finished_at.is_none())
         .for_each(|mut agent| {
-            if !agent.has_path() {
-                if let Some(node) = agent.frontier.pop() {
-                    let union_explored = agent
-                        .explored
-                        .union(&knowledge.explored_tiles)
-                        .map(|n| *n)
-                        .collect();
-                    agent.current_path = node_utils::find_know_path_to_node(
-                        agent.position,
-                        node,
-                        &maze,
-                        &union_explored,
-                    );
-                }
+            if !agent.has_path()
+                && agent.explores(&knowledge, &settings)
+                && let Some(node) = agent.frontier.pop()
+            {
+                let union_explored = agent.explored.union(&knowledge.explored_tiles);
+                agent.current_path = node_utils::find_know_path_to_node(
+                    agent.position,
+                    node,
+                    &maze,
+                    &union_explored,
+                );
             }
             if let Some(path) = &mut agent.current_path {
                 if let Some(next_node) = path.pop() {
+                    agent.previous_position = agent.position;
                     agent.position = next_node;
-                    if agent.position == maze.goal {
-                        knowledge.remaining_agents = knowledge.remaining_agents.saturating_sub(1);
-                        if knowledge.remaining_agents == 0 {
-                            step_data.stop();
-                            events.write(AgentsCompleteMazeEvent);
+                    if maze.is_goal(agent.position) {
+                        match settings.mission {
+                            Mission::AnyExit => {
+                                agent.finished_at = Some(step_data.timesteps);
+                                knowledge.remaining_agents =
+                                    knowledge.remaining_agents.saturating_sub(1);
+                                if knowledge.remaining_agents == 0 {
+                                    step_data.stop();
+                                    events.write(AgentsCompleteMazeEvent);
+                                }
+                            }
+                            Mission::CollectAll => {
+                                let goal = agent.position;
+                                agent.visited_goals.insert(goal);
+                                if agent.found_goal == Some(goal) {
+                                    agent.found_goal = None;
+                                }
+                                knowledge.visited_goals.insert(goal);
+                                all_goals_visited =
+                                    knowledge.visited_goals.len() == maze.goals.len();
+                            }
                         }
                     }
                     if settings.share_positions {
@@ -302,4 +531,13 @@ fn move_agent_path(
                 }
             }
         });
+    // the whole team is done once every goal was visited
+    if all_goals_visited {
+        query
+            .iter_mut()
+            .for_each(|mut agent| agent.finished_at = Some(step_data.timesteps));
+        knowledge.remaining_agents = 0;
+        step_data.stop();
+        events.write(AgentsCompleteMazeEvent);
+    }
 }
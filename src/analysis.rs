@@ -0,0 +1,204 @@
+use std::{collections::VecDeque, fs};
+
+use bevy::prelude::*;
+use rand::{SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    agent::Agent,
+    cli_args::AnalyzeArgs,
+    export,
+    maze::{Maze, Tile},
+    rng::GlobalRng,
+    settings::SimulationSettings,
+    utils::{
+        grid::{TileGrid, TileSet},
+        spawn_utils,
+    },
+};
+
+/// Structural metrics of a maze, to compare how hard mazes are beyond their size.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+pub struct MazeAnalysis {
+    pub open_tiles: usize,
+    /// Open tiles with a single open neighbor, not counting the goals
+    pub dead_ends: usize,
+    pub junctions: usize,
+    /// Average number of ways forward on the tiles that are not dead ends
+    pub branching_factor: f64,
+    /// Most tiles in a row with exactly two open neighbors
+    pub longest_corridor: usize,
+    /// Average shortest path length from every open tile to the closest goal
+    pub average_goal_distance: f64,
+    /// Shortest path length from the spawn of each agent to the closest goal
+    pub spawn_goal_distances: Vec<Option<usize>>,
+}
+
+impl MazeAnalysis {
+    pub fn create(maze: &Maze, spawns: &[(usize, usize)]) -> Self {
+        let open_tiles = maze
+            .tile_grid
+            .positions()
+            .filter(|(_, tile)| *tile != Tile::Wall)
+            .map(|(position, _)| position)
+            .collect::<Vec<(usize, usize)>>();
+        let degree = |position: (usize, usize)| open_neighbors(maze, position).count();
+        let dead_ends = open_tiles
+            .iter()
+            .filter(|p| degree(**p) == 1 && !maze.is_goal(**p))
+            .count();
+        let junctions = open_tiles.iter().filter(|p| degree(**p) >= 3).count();
+        let ways_forward = open_tiles
+            .iter()
+            .map(|p| degree(*p))
+            .filter(|degree| *degree >= 2)
+            .map(|degree| (degree - 1) as f64)
+            .collect::<Vec<f64>>();
+        let distances = goal_distances(maze);
+        let reachable = open_tiles
+            .iter()
+            .filter_map(|p| distances[*p])
+            .collect::<Vec<usize>>();
+        Self {
+            open_tiles: open_tiles.len(),
+            dead_ends,
+            junctions,
+            branching_factor: mean(&ways_forward),
+            longest_corridor: longest_corridor(maze, &open_tiles),
+            average_goal_distance: mean(&reachable.iter().map(|d| *d as f64).collect::<Vec<f64>>()),
+            spawn_goal_distances: spawns.iter().map(|p| distances[*p]).collect(),
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Open tiles:            {}", self.open_tiles);
+        println!("Dead ends:             {}", self.dead_ends);
+        println!("Junctions:             {}", self.junctions);
+        println!("Branching factor:      {:.3}", self.branching_factor);
+        println!("Longest corridor:      {}", self.longest_corridor);
+        println!("Average goal distance: {:.1}", self.average_goal_distance);
+        println!("Spawn goal distances:");
+        self.spawn_goal_distances
+            .iter()
+            .enumerate()
+            .for_each(|(id, distance)| match distance {
+                Some(distance) => println!("  agent {:<3} {}", id, distance),
+                None => println!("  agent {:<3} unreachable", id),
+            });
+    }
+}
+
+pub fn analyze_maze(mut commands: Commands, query: Query<&Agent>, maze: Res<Maze>) {
+    let mut agents = query.iter().collect::<Vec<&Agent>>();
+    agents.sort_by_key(|agent| agent.id);
+    let spawns = agents
+        .iter()
+        .map(|agent| agent.position)
+        .collect::<Vec<(usize, usize)>>();
+    commands.insert_resource(MazeAnalysis::create(&maze, &spawns));
+}
+
+pub fn run_analyze(args: &AnalyzeArgs) {
+    let maze = match &args.maze_file {
+        Some(path) => Maze::load(path).map_err(|e| format!("Invalid maze file: {}", e)),
+        None => export::generate_maze(&args.maze, args.seed),
+    }
+    .unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let settings = SimulationSettings {
+        num_agents: args.num_agents,
+        spawn_placement: args.spawn_placement,
+        spawn_seed: args.spawn_seed,
+        spawn_goal_distance: args.spawn_goal_distance,
+        ..export::create_maze_settings(&args.maze, args.seed)
+    };
+    // the same spawn positions as a simulation with this seed
+    let seeds = GlobalRng::new(args.seed, &settings);
+    let mut rng = StdRng::seed_from_u64(seeds.spawn_seed);
+    let spawns = spawn_utils::spawn_positions(&maze, maze.floor_positions(), &settings, &mut rng);
+    let analysis = MazeAnalysis::create(&maze, &spawns);
+    println!("Maze of {}x{} tiles", maze.width(), maze.height());
+    analysis.print();
+    if let Some(output_file) = &args.output_file {
+        let contents =
+            serde_json::to_string_pretty(&analysis).expect("Failed to create maze analysis");
+        fs::write(output_file, contents).expect("Failed to write to file!");
+    }
+}
+
+fn open_neighbors(maze: &Maze, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    [
+        (x.wrapping_sub(1), y),
+        (x + 1, y),
+        (x, y.wrapping_sub(1)),
+        (x, y + 1),
+    ]
+    .into_iter()
+    .filter(|neighbor| {
+        maze.tile_grid
+            .get(*neighbor)
+            .is_some_and(|tile| tile != Tile::Wall)
+    })
+}
+
+/// Shortest path length from every tile to the closest goal, `None` when no goal can be reached.
+pub fn goal_distances(maze: &Maze) -> TileGrid<Option<usize>> {
+    path_distances(maze, maze.goals.iter().copied())
+}
+
+/// Breadth first search from every source at once.
+pub fn path_distances(
+    maze: &Maze,
+    sources: impl IntoIterator<Item = (usize, usize)>,
+) -> TileGrid<Option<usize>> {
+    let mut distances = TileGrid::filled(maze.width(), maze.height(), None);
+    let mut queue = VecDeque::new();
+    sources.into_iter().for_each(|source| {
+        distances[source] = Some(0);
+        queue.push_back(source);
+    });
+    while let Some(position) = queue.pop_front() {
+        let distance = distances[position].unwrap_or(0);
+        open_neighbors(maze, position).for_each(|neighbor| {
+            if distances[neighbor].is_none() {
+                distances[neighbor] = Some(distance + 1);
+                queue.push_back(neighbor);
+            }
+        });
+    }
+    distances
+}
+
+fn longest_corridor(maze: &Maze, open_tiles: &[(usize, usize)]) -> usize {
+    let is_corridor = |position: (usize, usize)| open_neighbors(maze, position).count() == 2;
+    let mut visited = TileSet::new(maze.width(), maze.height());
+    let mut longest = 0;
+    for start in open_tiles.iter().filter(|p| is_corridor(**p)) {
+        if !visited.insert(*start) {
+            continue;
+        }
+        let mut length = 0;
+        let mut stack = vec![*start];
+        while let Some(position) = stack.pop() {
+            length += 1;
+            open_neighbors(maze, position)
+                .filter(|neighbor| is_corridor(*neighbor))
+                .for_each(|neighbor| {
+                    if visited.insert(neighbor) {
+                        stack.push(neighbor);
+                    }
+                });
+        }
+        longest = longest.max(length);
+    }
+    longest
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
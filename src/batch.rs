@@ -0,0 +1,101 @@
+use std::{
+    fs,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli_args::BatchArgs,
+    experiment::ExperimentConfig,
+    headless::{self, SimulationSummary},
+    settings::SimulationSettings,
+    statistics::{self, ConfigurationStatistics},
+};
+
+#[derive(Serialize, Deserialize)]
+struct BatchSummary {
+    experiment: ExperimentConfig,
+    statistics: Vec<ConfigurationStatistics>,
+    runs: Vec<SimulationSummary>,
+}
+
+pub fn run_batch(args: &BatchArgs) {
+    let experiment = match &args.config {
+        Some(path) => ExperimentConfig::load(path),
+        None => ExperimentConfig::create_from(args),
+    }
+    .unwrap_or_else(|e| {
+        eprintln!("Invalid experiment config: {}", e);
+        std::process::exit(1);
+    });
+    let runs = experiment.create_settings();
+    let jobs = args.jobs.min(runs.len()).max(1);
+    println!(
+        "Running {} simulations in batch mode with {} jobs",
+        runs.len(),
+        jobs
+    );
+    let summaries = run_parallel(runs, jobs);
+    let statistics = statistics::create_statistics(&experiment, &summaries);
+    println!();
+    statistics::print_statistics_table(&statistics);
+
+    let summary = BatchSummary {
+        experiment,
+        statistics,
+        runs: summaries,
+    };
+    let contents = serde_json::to_string_pretty(&summary).expect("Failed to create batch summary");
+    if let Some(output_file) = &args.output_file {
+        fs::write(output_file, contents).expect("Failed to write to file!");
+    }
+}
+
+/// Every run is an independent app with its own rng, so the runs are simply
+/// handed out to the workers and put back in order once they are done.
+fn run_parallel(runs: Vec<SimulationSettings>, jobs: usize) -> Vec<SimulationSummary> {
+    let total = runs.len();
+    let queue = Mutex::new(runs.into_iter().enumerate());
+    let completed = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(total));
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let next = queue.lock().expect("Batch queue poisoned").next();
+                    let Some((i, settings)) = next else {
+                        break;
+                    };
+                    let summary = headless::run_simulation(settings);
+                    let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    println!(
+                        "[{}/{}] agents: {:<2} map size: {:<5} seed: {:<10} -> {} timesteps{}",
+                        done,
+                        total,
+                        summary.configuration.num_agents,
+                        summary.configuration.map_size().to_string(),
+                        summary.configuration.seed,
+                        summary.results.timesteps,
+                        if summary.results.completed {
+                            ""
+                        } else {
+                            " (did not finish)"
+                        }
+                    );
+                    results
+                        .lock()
+                        .expect("Batch results poisoned")
+                        .push((i, summary));
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().expect("Batch results poisoned");
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, summary)| summary).collect()
+}
@@ -1,11 +1,27 @@
 use bevy::ecs::resource::Resource;
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 
-use crate::settings::SimulationSpeed;
+use crate::{
+    headless::CommunicationOptions,
+    settings::{
+        MapSize, MazeAlgorithm, Mission, Selectable, SimulationSpeed, SpawnPlacement, Strategy,
+        TeamGroup,
+    },
+};
+
+pub const MAX_AGENTS: u32 = 512;
+pub const MAX_MAP_SIZE: usize = 512;
+pub const MAX_SENSOR_RADIUS: usize = 32;
+
+pub const DEFAULT_COMMUNICATION_PRESETS: &str =
+    "none,positions,positions+goal,positions+goal+maze,all";
 
 #[derive(Parser, Resource, Clone)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
 pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(
         long,
         action,
@@ -13,21 +29,134 @@ pub struct CliArgs {
     )]
     pub headless: bool,
 
+    #[arg(
+        long,
+        requires("headless"),
+        conflicts_with("MazeArgs"),
+        help(
+            "Experiment configuration file (.toml or .json) describing a single run, instead of the simulation flags"
+        )
+    )]
+    pub config: Option<String>,
+
     #[arg(
         short,
         long,
         default_value_t = 2,
         value_parser = parse_agent_count,
+        conflicts_with("config"),
         help("The number of agents to spawn")
     )]
     pub num_agents: u32,
 
-    #[arg(short, long, default_value_t = 8, value_parser = parse_map_size, help("The size of the maze"))]
-    pub map_size: usize,
+    #[command(flatten)]
+    pub maze: MazeArgs,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help(
+            "Load the maze from a text file ('#' wall, 'G' goal, 'S' agent start) instead of generating it"
+        )
+    )]
+    pub maze_file: Option<String>,
+
+    #[arg(
+        long,
+        default_value("any-exit"),
+        conflicts_with("config"),
+        help("When the agents are done with the goals of the maze")
+    )]
+    pub mission: Mission,
+
+    #[arg(
+        long,
+        default_value("random"),
+        conflicts_with("config"),
+        help("Where the agents are placed when the simulation starts")
+    )]
+    pub spawn_placement: SpawnPlacement,
+
+    #[arg(
+        long,
+        default_value_t = 8,
+        conflicts_with("config"),
+        help(
+            "Minimum path length from the agents to the goals with the goal-distance spawn placement"
+        )
+    )]
+    pub spawn_goal_distance: usize,
 
     #[arg(
         long,
         action,
+        conflicts_with("config"),
+        help(
+            "Hide the goal positions from the agents until they see a goal or it is shared with them"
+        )
+    )]
+    pub unknown_goals: bool,
+
+    #[arg(
+        long,
+        default_value("heuristic"),
+        conflicts_with("config"),
+        help("How the agents pick the next tile to explore")
+    )]
+    pub strategy: Strategy,
+
+    #[arg(
+        long,
+        value_delimiter(','),
+        value_parser = TeamGroup::parse,
+        conflicts_with("config"),
+        help(
+            "Comma separated groups of agents with their own role and strategy, as COUNT:ROLE[:STRATEGY] (e.g 3:scout:nearest-frontier,2:follower), the other agents are scouts"
+        )
+    )]
+    pub team: Vec<TeamGroup>,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
+        help(
+            "Allocate the frontier tiles between the exploring agents, so no two head to the same one (needs map sharing)"
+        )
+    )]
+    pub coordinated: bool,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = parse_sensor_radius,
+        conflicts_with("config"),
+        help("How many tiles away the agents see")
+    )]
+    pub sensor_radius: usize,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
+        help(
+            "The agents only see along straight lines until a wall, instead of every tile in the sensor radius"
+        )
+    )]
+    pub line_of_sight: bool,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
+        help("The agents also see diagonally")
+    )]
+    pub diagonal_sensing: bool,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
         help("Disable agents from communicating the discovered goal position")
     )]
     pub disable_goal_sharing: bool,
@@ -35,6 +164,7 @@ pub struct CliArgs {
     #[arg(
         long,
         action,
+        conflicts_with("config"),
         help("Disable agents from communicating their positions")
     )]
     pub disable_position_sharing: bool,
@@ -42,6 +172,7 @@ pub struct CliArgs {
     #[arg(
         long,
         action,
+        conflicts_with("config"),
         help("Disable agents from sharing the discovered map tiles")
     )]
     pub disable_map_sharing: bool,
@@ -49,6 +180,7 @@ pub struct CliArgs {
     #[arg(
         long,
         action,
+        conflicts_with("config"),
         help("Disable agents from trying to help guiding others to exit")
     )]
     pub disable_agent_guiding: bool,
@@ -62,31 +194,509 @@ pub struct CliArgs {
     )]
     pub simulation_speed: SimulationSpeed,
 
+    #[arg(
+        long,
+        action,
+        help(
+            "Step the simulation as fast as possible instead of at the simulation speed (headless mode only)"
+        )
+    )]
+    pub unthrottled: bool,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help(
+            "Stop the simulation after this many timesteps, even if not every agent reached the goal"
+        )
+    )]
+    pub max_timesteps: Option<u64>,
+
     #[arg(
         short,
         long,
+        conflicts_with("config"),
         help("The seed to use to generate the maze and agent positions, leave empty of random")
     )]
     pub seed: Option<u64>,
 
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help("The seed of the maze layout, derived from the seed when left empty")
+    )]
+    pub maze_seed: Option<u64>,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help("The seed of the agent spawn positions, derived from the seed when left empty")
+    )]
+    pub spawn_seed: Option<u64>,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help("The seed of the agent behaviour, derived from the seed when left empty")
+    )]
+    pub behaviour_seed: Option<u64>,
+
     #[arg(short, long, help("Output file path to write the simulation results"))]
     pub output_file: Option<String>,
+
+    #[arg(
+        long,
+        help(
+            "Output file path (.csv or .jsonl) to write the state of the agents at every timestep"
+        )
+    )]
+    pub trace: Option<String>,
+
+    #[arg(
+        long,
+        help("Output file path to record the simulation for replaying it later")
+    )]
+    pub record: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with("headless"),
+        help("Replay a recorded simulation file instead of running the agents")
+    )]
+    pub replay: Option<String>,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    #[command(about("Run every combination of the given parameters in headless mode"))]
+    Batch(Box<BatchArgs>),
+    #[command(about("Generate a maze and save it as a text (.txt) or image (.png) file"))]
+    ExportMaze(ExportMazeArgs),
+    #[command(about("Report the dead ends, corridors and goal distances of a maze"))]
+    Analyze(AnalyzeArgs),
+}
+
+#[derive(Args, Clone)]
+pub struct BatchArgs {
+    #[arg(
+        long,
+        help("Experiment configuration file (.toml or .json) to use instead of the flags below")
+    )]
+    pub config: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        value_delimiter(','),
+        default_value("2"),
+        conflicts_with("config"),
+        value_parser = parse_agent_count,
+        help("Comma separated list of the number of agents to spawn")
+    )]
+    pub num_agents: Vec<u32>,
+
+    #[arg(
+        short,
+        long,
+        value_delimiter(','),
+        default_value("8"),
+        conflicts_with("config"),
+        value_parser = parse_map_size,
+        help("Comma separated list of maze sizes, each one a single size or WIDTHxHEIGHT")
+    )]
+    pub map_size: Vec<MapSize>,
+
+    #[arg(
+        long,
+        value_delimiter(','),
+        default_value("growing-tree-random"),
+        conflicts_with("config"),
+        help("Comma separated list of algorithms used to generate the maze")
+    )]
+    pub maze_algorithm: Vec<MazeAlgorithm>,
+
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        conflicts_with("config"),
+        value_parser = parse_wall_removal,
+        help("Fraction (0.0 to 1.0) of the interior maze walls to remove, creating loops")
+    )]
+    pub wall_removal: f64,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        conflicts_with("config"),
+        help("Number of rectangular rooms to carve into the maze")
+    )]
+    pub rooms: u32,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help(
+            "Load the maze of every simulation from a text file ('#' wall, 'G' goal, 'S' agent start)"
+        )
+    )]
+    pub maze_file: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        conflicts_with("config"),
+        value_parser = parse_goal_count,
+        help("The number of goals placed on the border of a generated maze")
+    )]
+    pub num_goals: u32,
+
+    #[arg(
+        long,
+        default_value("any-exit"),
+        conflicts_with("config"),
+        help("When the agents are done with the goals of the maze")
+    )]
+    pub mission: Mission,
+
+    #[arg(
+        long,
+        default_value("random"),
+        conflicts_with("config"),
+        help("Where the agents are placed when the simulation starts")
+    )]
+    pub spawn_placement: SpawnPlacement,
+
+    #[arg(
+        long,
+        default_value_t = 8,
+        conflicts_with("config"),
+        help(
+            "Minimum path length from the agents to the goals with the goal-distance spawn placement"
+        )
+    )]
+    pub spawn_goal_distance: usize,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
+        help(
+            "Hide the goal positions from the agents until they see a goal or it is shared with them"
+        )
+    )]
+    pub unknown_goals: bool,
+
+    #[arg(
+        long,
+        default_value("heuristic"),
+        conflicts_with("config"),
+        help("How the agents pick the next tile to explore")
+    )]
+    pub strategy: Strategy,
+
+    #[arg(
+        long,
+        value_delimiter(','),
+        value_parser = TeamGroup::parse,
+        conflicts_with("config"),
+        help(
+            "Comma separated groups of agents with their own role and strategy, as COUNT:ROLE[:STRATEGY] (e.g 3:scout:nearest-frontier,2:follower), the other agents are scouts"
+        )
+    )]
+    pub team: Vec<TeamGroup>,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
+        help(
+            "Allocate the frontier tiles between the exploring agents, so no two head to the same one (needs map sharing)"
+        )
+    )]
+    pub coordinated: bool,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = parse_sensor_radius,
+        conflicts_with("config"),
+        help("How many tiles away the agents see")
+    )]
+    pub sensor_radius: usize,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
+        help(
+            "The agents only see along straight lines until a wall, instead of every tile in the sensor radius"
+        )
+    )]
+    pub line_of_sight: bool,
+
+    #[arg(
+        long,
+        action,
+        conflicts_with("config"),
+        help("The agents also see diagonally")
+    )]
+    pub diagonal_sensing: bool,
+
+    #[arg(
+        short,
+        long,
+        required_unless_present("config"),
+        conflicts_with("config"),
+        value_delimiter(','),
+        help("Comma separated list of seeds to run for every configuration")
+    )]
+    pub seeds: Vec<u64>,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help("The seed of the maze layout, derived from each seed when left empty")
+    )]
+    pub maze_seed: Option<u64>,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help("The seed of the agent spawn positions, derived from each seed when left empty")
+    )]
+    pub spawn_seed: Option<u64>,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help("The seed of the agent behaviour, derived from each seed when left empty")
+    )]
+    pub behaviour_seed: Option<u64>,
+
+    #[arg(
+        short,
+        long,
+        value_delimiter(','),
+        default_value(DEFAULT_COMMUNICATION_PRESETS),
+        conflicts_with("config"),
+        value_parser = parse_communication_options,
+        help(
+            "Comma separated list of communication options, each one being 'none', 'all' or a '+' separated combination of 'goal', 'positions', 'maze' and 'guiding'"
+        )
+    )]
+    pub communication: Vec<String>,
+
+    #[arg(
+        long,
+        conflicts_with("config"),
+        help(
+            "Stop each simulation after this many timesteps, even if not every agent reached the goal (100 per tile of the maze by default)"
+        )
+    )]
+    pub max_timesteps: Option<u64>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 1,
+        value_parser = parse_job_count,
+        help("The number of simulations to run at the same time")
+    )]
+    pub jobs: usize,
+
+    #[arg(short, long, help("Output file path to write the aggregated results"))]
+    pub output_file: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct MazeArgs {
+    #[arg(
+        short,
+        long,
+        default_value("8"),
+        value_parser = parse_map_size,
+        help("The size of the maze, a single size for a square maze or WIDTHxHEIGHT (e.g 32x8)")
+    )]
+    pub map_size: MapSize,
+
+    #[arg(
+        long,
+        default_value("growing-tree-random"),
+        help("The algorithm used to generate the maze")
+    )]
+    pub maze_algorithm: MazeAlgorithm,
+
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        value_parser = parse_wall_removal,
+        help("Fraction (0.0 to 1.0) of the interior maze walls to remove, creating loops")
+    )]
+    pub wall_removal: f64,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help("Number of rectangular rooms to carve into the maze")
+    )]
+    pub rooms: u32,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = parse_goal_count,
+        help("The number of goals placed on the border of a generated maze")
+    )]
+    pub num_goals: u32,
+}
+
+#[derive(Args, Clone)]
+pub struct ExportMazeArgs {
+    #[arg(short, long, help("The seed to use to generate the maze"))]
+    pub seed: u64,
+
+    #[command(flatten)]
+    pub maze: MazeArgs,
+
+    #[arg(short, long, help("Output file path (.txt or .png)"))]
+    pub output_file: String,
+}
+
+#[derive(Args, Clone)]
+pub struct AnalyzeArgs {
+    #[arg(short, long, help("The seed to use to generate the maze"))]
+    pub seed: u64,
+
+    #[command(flatten)]
+    pub maze: MazeArgs,
+
+    #[arg(
+        long,
+        help(
+            "Analyze a maze text file ('#' wall, 'G' goal, 'S' agent start) instead of generating it"
+        )
+    )]
+    pub maze_file: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 2,
+        value_parser = parse_agent_count,
+        help("The number of agents to measure the spawn distances of")
+    )]
+    pub num_agents: u32,
+
+    #[arg(
+        long,
+        default_value("random"),
+        help("Where the agents are placed when the simulation starts")
+    )]
+    pub spawn_placement: SpawnPlacement,
+
+    #[arg(
+        long,
+        default_value_t = 8,
+        help(
+            "Minimum path length from the agents to the goals with the goal-distance spawn placement"
+        )
+    )]
+    pub spawn_goal_distance: usize,
+
+    #[arg(
+        long,
+        help("The seed of the agent spawn positions, derived from the seed when left empty")
+    )]
+    pub spawn_seed: Option<u64>,
+
+    #[arg(short, long, help("Output file path to write the analysis as JSON"))]
+    pub output_file: Option<String>,
 }
 
 fn parse_agent_count(s: &str) -> Result<u32, String> {
-    let count = s.parse().map_err(|e| format!("{}", e))?;
-    if count < 1 || count > 32 {
-        return Err(String::from(
-            "Number of agents needs to be between 2 and 32",
+    validate_agent_count(s.parse().map_err(|e| format!("{}", e))?)
+}
+
+pub fn validate_agent_count(count: u32) -> Result<u32, String> {
+    if !(1..=MAX_AGENTS).contains(&count) {
+        return Err(format!(
+            "Number of agents needs to be between 1 and {}",
+            MAX_AGENTS
         ));
     }
     Ok(count)
 }
 
-fn parse_map_size(s: &str) -> Result<usize, String> {
-    let size = s.parse().map_err(|e| format!("{}", e))?;
-    if size < 8 || size > 64 {
-        return Err(String::from("Map size needs to be between 8 and 64"));
+fn parse_map_size(s: &str) -> Result<MapSize, String> {
+    validate_map_size(MapSize::parse(s)?)
+}
+
+pub fn validate_map_size(size: MapSize) -> Result<MapSize, String> {
+    if size.width < 8 || size.width > MAX_MAP_SIZE || size.height < 8 || size.height > MAX_MAP_SIZE
+    {
+        return Err(format!(
+            "Map width and height need to be between 8 and {}",
+            MAX_MAP_SIZE
+        ));
     }
     Ok(size)
 }
+
+fn parse_wall_removal(s: &str) -> Result<f64, String> {
+    validate_wall_removal(s.parse().map_err(|e| format!("{}", e))?)
+}
+
+pub fn validate_wall_removal(fraction: f64) -> Result<f64, String> {
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(String::from("Wall removal needs to be between 0.0 and 1.0"));
+    }
+    Ok(fraction)
+}
+
+fn parse_goal_count(s: &str) -> Result<u32, String> {
+    validate_goal_count(s.parse().map_err(|e| format!("{}", e))?)
+}
+
+pub fn validate_goal_count(count: u32) -> Result<u32, String> {
+    if !(1..=16).contains(&count) {
+        return Err(String::from("Number of goals needs to be between 1 and 16"));
+    }
+    Ok(count)
+}
+
+pub fn validate_seeded_algorithm(algorithm: MazeAlgorithm) -> Result<MazeAlgorithm, String> {
+    if !algorithm.is_reproducible() {
+        return Err(format!(
+            "The {} maze algorithm generates a different maze on every run, it can not be used with a seed",
+            algorithm.as_str()
+        ));
+    }
+    Ok(algorithm)
+}
+
+fn parse_sensor_radius(s: &str) -> Result<usize, String> {
+    validate_sensor_radius(s.parse().map_err(|e| format!("{}", e))?)
+}
+
+pub fn validate_sensor_radius(radius: usize) -> Result<usize, String> {
+    if !(1..=MAX_SENSOR_RADIUS).contains(&radius) {
+        return Err(format!(
+            "Sensor radius needs to be between 1 and {}",
+            MAX_SENSOR_RADIUS
+        ));
+    }
+    Ok(radius)
+}
+
+fn parse_job_count(s: &str) -> Result<usize, String> {
+    let count = s.parse().map_err(|e| format!("{}", e))?;
+    if count < 1 {
+        return Err(String::from("Number of jobs needs to be at least 1"));
+    }
+    Ok(count)
+}
+
+fn parse_communication_options(s: &str) -> Result<String, String> {
+    CommunicationOptions::parse(s)?;
+    Ok(s.to_string())
+}
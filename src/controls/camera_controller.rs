@@ -40,10 +40,9 @@ fn spawn_camera(mut commands: Commands) {
 
 fn center_camera_on_maze(mut query: Query<&mut Transform, With<Camera2d>>, maze: Res<Maze>) {
     query.iter_mut().for_each(|mut transform| {
-        let maze_size = maze.tile_grid.len();
-        let dim = (maze_size * TILE_SIZE + TILE_GAP * (maze_size - 1)) as f32;
-        transform.translation.x = dim / 2.0;
-        transform.translation.y = dim / 2.0;
+        let dim = |tiles: usize| (tiles * TILE_SIZE + TILE_GAP * (tiles - 1)) as f32;
+        transform.translation.x = dim(maze.width()) / 2.0;
+        transform.translation.y = dim(maze.height()) / 2.0;
     });
 }
 
@@ -1,2 +1,3 @@
 pub mod camera_controller;
 pub mod simulation_controller;
+pub mod timeline_controller;
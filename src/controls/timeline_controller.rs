@@ -0,0 +1,227 @@
+use std::collections::VecDeque;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use rand::rngs::StdRng;
+
+use crate::{
+    GameState,
+    agent::{self, Agent, SharedMazeKnowledge},
+    metrics::{self, AgentMetrics, MazeGoalMetrics},
+    replay::{self, ReplayRecorder},
+    rng::GlobalRng,
+    simulation::{SimulationStepData, SimulationStepEvent},
+    trace::TraceWriter,
+};
+
+/// How much memory the snapshots may use before the oldest ones are dropped.
+const MAX_HISTORY_BYTES: usize = 256 * 1024 * 1024;
+
+pub struct TimelineControllerPlugin;
+
+impl Plugin for TimelineControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulationHistory::default());
+        app.add_event::<TimelineSeekEvent>();
+        app.add_systems(
+            OnEnter(GameState::Simulation),
+            reset_history
+                .after(agent::setup_shared_knowledge)
+                .after(metrics::attach_agent_metrics)
+                .after(metrics::reset_goal_metrics),
+        );
+        app.add_systems(
+            FixedUpdate,
+            take_snapshot
+                .after(agent::move_agent_path)
+                .after(replay::play_replay)
+                .run_if(on_event::<SimulationStepEvent>),
+        );
+        app.add_systems(
+            Update,
+            (
+                previous_step.run_if(input_just_pressed(KeyCode::ArrowLeft)),
+                restore_snapshot
+                    .after(previous_step)
+                    .run_if(on_event::<TimelineSeekEvent>),
+                rewind_outputs
+                    .after(restore_snapshot)
+                    .run_if(on_event::<TimelineSeekEvent>),
+            )
+                .run_if(in_state(GameState::Simulation)),
+        );
+    }
+}
+
+/// Asks to go back (or forward) to the state of an already simulated timestep.
+#[derive(Event)]
+pub struct TimelineSeekEvent(pub u64);
+
+/// The state of the simulation at the timesteps simulated so far, the oldest
+/// ones are dropped once they use more than `MAX_HISTORY_BYTES`.
+#[derive(Resource, Default)]
+pub struct SimulationHistory {
+    first_timestep: u64,
+    snapshots: VecDeque<Snapshot>,
+    bytes: usize,
+}
+
+impl SimulationHistory {
+    pub fn first_timestep(&self) -> u64 {
+        self.first_timestep
+    }
+
+    pub fn last_timestep(&self) -> u64 {
+        self.first_timestep + self.snapshots.len().saturating_sub(1) as u64
+    }
+
+    fn get(&self, timestep: u64) -> Option<&Snapshot> {
+        let index = timestep.checked_sub(self.first_timestep)?;
+        self.snapshots.get(index as usize)
+    }
+
+    fn push(&mut self, timestep: u64, snapshot: Snapshot) {
+        // after going back in time the simulation is deterministic, so the
+        // following timesteps are simply simulated and stored again
+        let kept = timestep.saturating_sub(self.first_timestep) as usize;
+        while self.snapshots.len() > kept {
+            if let Some(dropped) = self.snapshots.pop_back() {
+                self.bytes -= dropped.bytes;
+            }
+        }
+        self.bytes += snapshot.bytes;
+        self.snapshots.push_back(snapshot);
+        while self.bytes > MAX_HISTORY_BYTES && self.snapshots.len() > 1 {
+            if let Some(dropped) = self.snapshots.pop_front() {
+                self.bytes -= dropped.bytes;
+                self.first_timestep += 1;
+            }
+        }
+    }
+}
+
+struct Snapshot {
+    /// Sorted by the id of the agents
+    agents: Vec<(Agent, AgentMetrics)>,
+    knowledge: SharedMazeKnowledge,
+    goal_metrics: MazeGoalMetrics,
+    rng: StdRng,
+    stopped: bool,
+    /// Rough memory use, counting the tile sets and the paths of the agents
+    bytes: usize,
+}
+
+fn create_snapshot(
+    query: &Query<(&Agent, &AgentMetrics)>,
+    knowledge: &SharedMazeKnowledge,
+    goal_metrics: &MazeGoalMetrics,
+    rng: &GlobalRng,
+    step: &SimulationStepData,
+) -> Snapshot {
+    let mut agents = query
+        .iter()
+        .map(|(agent, metrics)| (agent.clone(), metrics.clone()))
+        .collect::<Vec<(Agent, AgentMetrics)>>();
+    agents.sort_by_key(|(agent, _)| agent.id);
+    let position_size = size_of::<(usize, usize)>();
+    let bytes = knowledge.explored_tiles.byte_size()
+        + agents
+            .iter()
+            .map(|(agent, _)| {
+                let path_length = agent.current_path.as_ref().map_or(0, |path| path.len());
+                // the seen tiles and the metrics keep tile sets as large as the explored one
+                3 * agent.explored.byte_size()
+                    + (agent.frontier.len() + path_length) * position_size
+            })
+            .sum::<usize>();
+    Snapshot {
+        agents,
+        knowledge: knowledge.clone(),
+        goal_metrics: goal_metrics.clone(),
+        rng: rng.rng.clone(),
+        stopped: step.stopped,
+        bytes,
+    }
+}
+
+fn reset_history(
+    mut history: ResMut<SimulationHistory>,
+    query: Query<(&Agent, &AgentMetrics)>,
+    knowledge: Res<SharedMazeKnowledge>,
+    goal_metrics: Res<MazeGoalMetrics>,
+    rng: Res<GlobalRng>,
+    step: Res<SimulationStepData>,
+) {
+    *history = SimulationHistory {
+        first_timestep: step.timesteps,
+        ..default()
+    };
+    let snapshot = create_snapshot(&query, &knowledge, &goal_metrics, &rng, &step);
+    history.push(step.timesteps, snapshot);
+}
+
+fn take_snapshot(
+    mut history: ResMut<SimulationHistory>,
+    query: Query<(&Agent, &AgentMetrics)>,
+    knowledge: Res<SharedMazeKnowledge>,
+    goal_metrics: Res<MazeGoalMetrics>,
+    rng: Res<GlobalRng>,
+    step: Res<SimulationStepData>,
+) {
+    let snapshot = create_snapshot(&query, &knowledge, &goal_metrics, &rng, &step);
+    history.push(step.timesteps, snapshot);
+}
+
+fn previous_step(step: Res<SimulationStepData>, mut events: EventWriter<TimelineSeekEvent>) {
+    if step.timesteps > 0 {
+        events.write(TimelineSeekEvent(step.timesteps - 1));
+    }
+}
+
+fn restore_snapshot(
+    mut events: EventReader<TimelineSeekEvent>,
+    history: Res<SimulationHistory>,
+    mut query: Query<(&mut Agent, &mut AgentMetrics)>,
+    mut knowledge: ResMut<SharedMazeKnowledge>,
+    mut goal_metrics: ResMut<MazeGoalMetrics>,
+    mut rng: ResMut<GlobalRng>,
+    mut step: ResMut<SimulationStepData>,
+) {
+    let Some(TimelineSeekEvent(timestep)) = events.read().last() else {
+        return;
+    };
+    let Some(snapshot) = history.get(*timestep) else {
+        return;
+    };
+    query.iter_mut().for_each(|(mut agent, mut metrics)| {
+        let saved = snapshot
+            .agents
+            .binary_search_by_key(&agent.id, |(saved_agent, _)| saved_agent.id);
+        if let Ok(i) = saved {
+            let (saved_agent, saved_metrics) = &snapshot.agents[i];
+            *agent = saved_agent.clone();
+            *metrics = saved_metrics.clone();
+        }
+    });
+    *knowledge = snapshot.knowledge.clone();
+    *goal_metrics = snapshot.goal_metrics.clone();
+    rng.rng = snapshot.rng.clone();
+    step.timesteps = *timestep;
+    step.stopped = snapshot.stopped;
+    step.paused = true;
+}
+
+/// The recording and the trace continue from the restored timestep.
+fn rewind_outputs(
+    step: Res<SimulationStepData>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    trace: Option<ResMut<TraceWriter>>,
+) {
+    if let Some(mut recorder) = recorder {
+        recorder.rewind(step.timesteps);
+    }
+    if let Some(mut trace) = trace {
+        trace
+            .rewind(step.timesteps)
+            .expect("Failed to write to trace file!");
+    }
+}
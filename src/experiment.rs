@@ -0,0 +1,284 @@
+use std::{fmt, fs, path::Path};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{MapAccess, Visitor},
+};
+
+use crate::{
+    cli_args::{self, BatchArgs, DEFAULT_COMMUNICATION_PRESETS},
+    headless::CommunicationOptions,
+    maze::Maze,
+    settings::{
+        MapSize, MazeAlgorithm, Mission, SeedType, Sensor, SimulationSettings, SpawnPlacement,
+        Strategy, TeamGroup,
+    },
+};
+
+/// Describes every simulation of a batch, either built from the command line
+/// flags or loaded from a `.toml`/`.json` file, e.g.:
+///
+/// ```toml
+/// num_agents = [2, 5]
+/// map_size = [8, "32x8"]
+/// maze_algorithm = ["growing-tree-random", "kruskal"]
+/// seeds = [3, 1234, 1337]
+///
+/// [communication.nothing]
+/// share_goal = false
+/// share_positions = false
+/// share_maze = false
+/// agent_guiding = false
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExperimentConfig {
+    pub num_agents: Vec<u32>,
+    pub map_size: Vec<MapSize>,
+    pub maze_algorithm: Vec<MazeAlgorithm>,
+    /// Fraction of the interior walls knocked down in every maze, creating loops
+    pub wall_removal: f64,
+    /// Rectangular rooms carved into every maze
+    pub rooms: u32,
+    /// A hand-authored maze replacing the generated ones
+    pub maze_file: Option<String>,
+    /// Goals placed on the border of every generated maze
+    pub num_goals: u32,
+    pub mission: Mission,
+    pub spawn_placement: SpawnPlacement,
+    /// Minimum path length from the agents to the goals with the goal-distance spawn placement
+    pub spawn_goal_distance: usize,
+    /// Hides the goals from the agents until they find them
+    pub unknown_goals: bool,
+    /// How the agents explore, unless their team group has its own strategy
+    pub strategy: Strategy,
+    /// Groups of agents with their own role and strategy, e.g.
+    /// `["3:scout:nearest-frontier", "2:follower"]`
+    pub team: Vec<TeamGroup>,
+    /// Allocates the frontier tiles between the agents
+    pub coordinated: bool,
+    /// What the agents see, a `[sensor]` table with `radius`, `line_of_sight` and `diagonal`
+    pub sensor: Sensor,
+    pub seeds: Vec<u64>,
+    /// Replaces the maze seed derived from each seed, e.g. to run every seed on the same maze
+    pub maze_seed: Option<u64>,
+    /// Replaces the spawn seed derived from each seed
+    pub spawn_seed: Option<u64>,
+    /// Replaces the behaviour seed derived from each seed
+    pub behaviour_seed: Option<u64>,
+    /// Named communication presets, run in the order they are written in
+    #[serde(with = "presets")]
+    pub communication: Vec<(String, CommunicationOptions)>,
+    /// Stops runs where agents get stuck, a batch stops them after 100 timesteps
+    /// per tile of the maze when left empty
+    pub max_timesteps: Option<u64>,
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        let communication = DEFAULT_COMMUNICATION_PRESETS
+            .split(',')
+            .map(|preset| {
+                let options = CommunicationOptions::parse(preset).expect("Invalid default preset");
+                (preset.to_string(), options)
+            })
+            .collect();
+        Self {
+            num_agents: vec![2],
+            map_size: vec![MapSize::new(8, 8)],
+            maze_algorithm: vec![MazeAlgorithm::default()],
+            wall_removal: 0.0,
+            rooms: 0,
+            maze_file: None,
+            num_goals: 1,
+            mission: Mission::AnyExit,
+            spawn_placement: SpawnPlacement::Random,
+            spawn_goal_distance: 8,
+            unknown_goals: false,
+            strategy: Strategy::Heuristic,
+            team: Vec::new(),
+            coordinated: false,
+            sensor: Sensor::default(),
+            seeds: Vec::new(),
+            maze_seed: None,
+            spawn_seed: None,
+            behaviour_seed: None,
+            communication,
+            max_timesteps: None,
+        }
+    }
+}
+
+impl ExperimentConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file '{}': {}", path, e))?;
+        let config: Self = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|e| format!("{}", e))?,
+            Some("json") => serde_json::from_str(&contents).map_err(|e| format!("{}", e))?,
+            _ => {
+                return Err(String::from(
+                    "Config file needs to be a .toml or .json file",
+                ));
+            }
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn create_from(args: &BatchArgs) -> Result<Self, String> {
+        let communication = args
+            .communication
+            .iter()
+            .map(|preset| {
+                let options = CommunicationOptions::parse(preset).expect("Invalid preset");
+                (preset.clone(), options)
+            })
+            .collect();
+        let config = Self {
+            num_agents: args.num_agents.clone(),
+            map_size: args.map_size.clone(),
+            maze_algorithm: args.maze_algorithm.clone(),
+            wall_removal: args.wall_removal,
+            rooms: args.rooms,
+            maze_file: args.maze_file.clone(),
+            num_goals: args.num_goals,
+            mission: args.mission,
+            spawn_placement: args.spawn_placement,
+            spawn_goal_distance: args.spawn_goal_distance,
+            unknown_goals: args.unknown_goals,
+            strategy: args.strategy,
+            team: args.team.clone(),
+            coordinated: args.coordinated,
+            sensor: Sensor {
+                radius: args.sensor_radius,
+                line_of_sight: args.line_of_sight,
+                diagonal: args.diagonal_sensing,
+            },
+            seeds: args.seeds.clone(),
+            maze_seed: args.maze_seed,
+            spawn_seed: args.spawn_seed,
+            behaviour_seed: args.behaviour_seed,
+            communication,
+            max_timesteps: args.max_timesteps,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for count in &self.num_agents {
+            cli_args::validate_agent_count(*count)?;
+        }
+        for size in &self.map_size {
+            cli_args::validate_map_size(*size)?;
+        }
+        if self.num_agents.is_empty() || self.map_size.is_empty() {
+            return Err(String::from(
+                "At least one agent count and map size is needed",
+            ));
+        }
+        cli_args::validate_wall_removal(self.wall_removal)?;
+        cli_args::validate_goal_count(self.num_goals)?;
+        cli_args::validate_sensor_radius(self.sensor.radius)?;
+        let maze_file = self.maze_file.as_deref().map(Maze::load).transpose()?;
+        if self.maze_algorithm.is_empty() {
+            return Err(String::from("At least one maze algorithm is needed"));
+        }
+        if self.seeds.is_empty() {
+            return Err(String::from("At least one seed is needed"));
+        }
+        if self.communication.is_empty() {
+            return Err(String::from("At least one communication preset is needed"));
+        }
+        for (i, (name, _)) in self.communication.iter().enumerate() {
+            if self.communication[..i]
+                .iter()
+                .any(|(other, _)| other == name)
+            {
+                return Err(format!("Duplicate communication preset '{}'", name));
+            }
+        }
+        for settings in self.create_settings() {
+            settings.validate(maze_file.as_ref())?;
+        }
+        Ok(())
+    }
+
+    pub fn create_settings(&self) -> Vec<SimulationSettings> {
+        let mut runs = Vec::new();
+        for num_agents in &self.num_agents {
+            for map_size in &self.map_size {
+                for maze_algorithm in &self.maze_algorithm {
+                    for (_, communication) in &self.communication {
+                        for seed in &self.seeds {
+                            let mut settings = SimulationSettings {
+                                map_seed: SeedType::Selected(*seed),
+                                maze_seed: self.maze_seed,
+                                spawn_seed: self.spawn_seed,
+                                behaviour_seed: self.behaviour_seed,
+                                num_agents: *num_agents,
+                                grid_width: map_size.width,
+                                grid_height: map_size.height,
+                                maze_algorithm: *maze_algorithm,
+                                wall_removal: self.wall_removal,
+                                rooms: self.rooms,
+                                maze_file: self.maze_file.clone(),
+                                num_goals: self.num_goals,
+                                mission: self.mission,
+                                spawn_placement: self.spawn_placement,
+                                spawn_goal_distance: self.spawn_goal_distance,
+                                unknown_goals: self.unknown_goals,
+                                strategy: self.strategy,
+                                team: self.team.clone(),
+                                coordinated: self.coordinated,
+                                sensor: self.sensor,
+                                max_timesteps: self.max_timesteps,
+                                ..Default::default()
+                            };
+                            communication.apply_to(&mut settings);
+                            runs.push(settings);
+                        }
+                    }
+                }
+            }
+        }
+        runs
+    }
+}
+
+/// (De)serializes the communication presets as a map, keeping them in order.
+mod presets {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        presets: &[(String, CommunicationOptions)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(presets.iter().map(|(name, options)| (name, options)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, CommunicationOptions)>, D::Error> {
+        deserializer.deserialize_map(PresetVisitor)
+    }
+
+    struct PresetVisitor;
+
+    impl<'de> Visitor<'de> for PresetVisitor {
+        type Value = Vec<(String, CommunicationOptions)>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map of communication presets")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut presets = Vec::new();
+            while let Some(preset) = map.next_entry()? {
+                presets.push(preset);
+            }
+            Ok(presets)
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::{fs, path::Path};
+
+use image::{Rgb, RgbImage};
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::{
+    cli_args::{self, ExportMazeArgs, MazeArgs},
+    maze::{self, Maze, Tile},
+    settings::{SeedType, SimulationSettings},
+};
+
+const PIXELS_PER_TILE: u32 = 16;
+
+pub fn run_export_maze(args: &ExportMazeArgs) {
+    let maze = generate_maze(&args.maze, args.seed).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    if let Err(e) = save_maze(&maze, &args.output_file) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    println!("Maze saved to {}", args.output_file);
+}
+
+/// Generates the same maze as a simulation with the seed and maze options.
+pub fn generate_maze(args: &MazeArgs, seed: u64) -> Result<Maze, String> {
+    cli_args::validate_seeded_algorithm(args.maze_algorithm)?;
+    let settings = create_maze_settings(args, seed);
+    // same rng as the maze of a simulation with this seed, so the post-processing matches
+    let mut rng = StdRng::seed_from_u64(seed);
+    maze::generate_maze(&settings, seed, &mut rng)
+}
+
+pub fn create_maze_settings(args: &MazeArgs, seed: u64) -> SimulationSettings {
+    SimulationSettings {
+        map_seed: SeedType::Selected(seed),
+        grid_width: args.map_size.width,
+        grid_height: args.map_size.height,
+        maze_algorithm: args.maze_algorithm,
+        wall_removal: args.wall_removal,
+        rooms: args.rooms,
+        num_goals: args.num_goals,
+        ..Default::default()
+    }
+}
+
+fn save_maze(maze: &Maze, path: &str) -> Result<(), String> {
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("txt") => {
+            let mut contents = maze.to_ascii().join("\n");
+            contents.push('\n');
+            fs::write(path, contents).map_err(|e| format!("Failed to write to file: {}", e))
+        }
+        Some("png") => maze_image(maze)
+            .save(path)
+            .map_err(|e| format!("Failed to write to file: {}", e)),
+        _ => Err(String::from("Maze file needs to be a .txt or .png file")),
+    }
+}
+
+/// Draws every tile as a square with the colors of the maze render.
+fn maze_image(maze: &Maze) -> RgbImage {
+    let height = maze.height() as u32;
+    let width = maze.width() as u32;
+    RgbImage::from_fn(width * PIXELS_PER_TILE, height * PIXELS_PER_TILE, |x, y| {
+        let position = (
+            (x / PIXELS_PER_TILE) as usize,
+            (y / PIXELS_PER_TILE) as usize,
+        );
+        let tile = maze.tile_grid[position];
+        match tile {
+            Tile::Wall => Rgb([0, 0, 0]),
+            Tile::Floor => Rgb([255, 255, 255]),
+            Tile::Goal => Rgb([255, 255, 0]),
+        }
+    })
+}
@@ -1,62 +1,188 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-};
+use std::{collections::BTreeMap, fs};
 
-use bevy::prelude::*;
+use bevy::{prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    CliArgs, GameState,
+    CliArgs, GameState, MamofCorePlugins,
     agent::{Agent, AgentsCompleteMazeEvent},
+    analysis::MazeAnalysis,
+    experiment::ExperimentConfig,
+    maze::Maze,
+    metrics::{AgentMetrics, GoalMetrics, MazeGoalMetrics},
     rng::GlobalRng,
-    settings::{SeedType, SimulationSettings},
-    simulation::SimulationStepData,
+    settings::{
+        MapSize, MazeAlgorithm, Mission, SeedType, Sensor, SimulationSettings, SpawnPlacement,
+        Strategy, TeamGroup,
+    },
+    simulation::{SimulationStepData, SimulationTimeoutEvent},
+    utils::grid::TileSet,
 };
 
 #[derive(Serialize, Deserialize)]
-struct SimulationSummary {
-    configuration: SimulationConfiguration,
-    results: SimulationResults,
+pub struct SimulationSummary {
+    pub configuration: SimulationConfiguration,
+    pub results: SimulationResults,
 }
 
 #[derive(Serialize, Deserialize)]
-struct SimulationResults {
-    timesteps: u64,
-    total_unique_explored_titles: usize,
-    agent_explored_tiles: HashMap<usize, usize>,
+pub struct SimulationResults {
+    pub completed: bool,
+    pub timesteps: u64,
+    pub agents_finished: usize,
+    pub agent_finish_timesteps: BTreeMap<usize, Option<u64>>,
+    pub total_unique_explored_titles: usize,
+    pub agent_explored_tiles: BTreeMap<usize, usize>,
+    pub agent_metrics: BTreeMap<usize, AgentMetrics>,
+    #[serde(default)]
+    pub goal_metrics: Vec<GoalMetrics>,
+    #[serde(default)]
+    pub maze_analysis: MazeAnalysis,
 }
 
 #[derive(Serialize, Deserialize)]
-struct SimulationConfiguration {
-    num_agents: u32,
-    map_size: usize,
-    seed: u64,
-    communication_options: CommunicationOptions,
+pub struct SimulationConfiguration {
+    pub num_agents: u32,
+    pub map_width: usize,
+    pub map_height: usize,
+    /// The size of the maze in tiles, walls included, which is the only size of a maze file
+    #[serde(default)]
+    pub tile_width: usize,
+    #[serde(default)]
+    pub tile_height: usize,
+    #[serde(default)]
+    pub maze_algorithm: MazeAlgorithm,
+    #[serde(default)]
+    pub wall_removal: f64,
+    #[serde(default)]
+    pub rooms: u32,
+    #[serde(default)]
+    pub maze_file: Option<String>,
+    #[serde(default = "default_num_goals")]
+    pub num_goals: u32,
+    #[serde(default)]
+    pub mission: Mission,
+    #[serde(default)]
+    pub spawn_placement: SpawnPlacement,
+    #[serde(default)]
+    pub spawn_goal_distance: usize,
+    #[serde(default)]
+    pub unknown_goals: bool,
+    #[serde(default)]
+    pub strategy: Strategy,
+    #[serde(default)]
+    pub team: Vec<TeamGroup>,
+    #[serde(default)]
+    pub coordinated: bool,
+    #[serde(default)]
+    pub sensor: Sensor,
+    pub seed: u64,
+    #[serde(default)]
+    pub maze_seed: u64,
+    #[serde(default)]
+    pub spawn_seed: u64,
+    #[serde(default)]
+    pub behaviour_seed: u64,
+    pub communication_options: CommunicationOptions,
+    pub max_timesteps: Option<u64>,
 }
 
 impl SimulationConfiguration {
-    pub fn create_from(seed: u64, settings: &SimulationSettings) -> Self {
+    pub fn create_from(rng: &GlobalRng, settings: &SimulationSettings, maze: &Maze) -> Self {
         Self {
             num_agents: settings.num_agents,
-            map_size: settings.grid_size,
-            seed,
-            communication_options: CommunicationOptions {
-                share_goal: settings.share_goal,
-                share_positions: settings.share_positions,
-                share_maze: settings.share_tiles,
-                agent_guiding: settings.enable_guiding,
-            },
+            map_width: settings.grid_width,
+            map_height: settings.grid_height,
+            tile_width: maze.width(),
+            tile_height: maze.height(),
+            maze_algorithm: settings.maze_algorithm,
+            wall_removal: settings.wall_removal,
+            rooms: settings.rooms,
+            maze_file: settings.maze_file.clone(),
+            num_goals: settings.num_goals,
+            mission: settings.mission,
+            spawn_placement: settings.spawn_placement,
+            spawn_goal_distance: settings.spawn_goal_distance,
+            unknown_goals: settings.unknown_goals,
+            strategy: settings.strategy,
+            team: settings.team.clone(),
+            // the frontier tiles are only allocated when sharing the maze
+            coordinated: settings.coordinated && settings.share_tiles,
+            sensor: settings.sensor,
+            seed: rng.seed,
+            maze_seed: rng.maze_seed,
+            spawn_seed: rng.spawn_seed,
+            behaviour_seed: rng.behaviour_seed,
+            communication_options: CommunicationOptions::create_from(settings),
+            max_timesteps: settings.max_timesteps,
         }
     }
+
+    pub fn map_size(&self) -> MapSize {
+        MapSize::new(self.map_width, self.map_height)
+    }
 }
 
-#[derive(Serialize, Deserialize)]
-struct CommunicationOptions {
-    share_goal: bool,
-    share_positions: bool,
-    share_maze: bool,
-    agent_guiding: bool,
+fn default_num_goals() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CommunicationOptions {
+    pub share_goal: bool,
+    pub share_positions: bool,
+    pub share_maze: bool,
+    pub agent_guiding: bool,
+}
+
+impl CommunicationOptions {
+    pub fn create_from(settings: &SimulationSettings) -> Self {
+        Self {
+            share_goal: settings.share_goal,
+            share_positions: settings.share_positions,
+            share_maze: settings.share_tiles,
+            agent_guiding: settings.enable_guiding,
+        }
+    }
+
+    pub fn apply_to(&self, settings: &mut SimulationSettings) {
+        settings.share_goal = self.share_goal;
+        settings.share_positions = self.share_positions;
+        settings.share_tiles = self.share_maze;
+        settings.enable_guiding = self.agent_guiding;
+    }
+
+    /// Parses either `none`, `all` or a `+` separated combination of
+    /// `goal`, `positions`, `maze` and `guiding`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut options = Self {
+            share_goal: false,
+            share_positions: false,
+            share_maze: false,
+            agent_guiding: false,
+        };
+        match s {
+            "none" => {}
+            "all" => {
+                options.share_goal = true;
+                options.share_positions = true;
+                options.share_maze = true;
+                options.agent_guiding = true;
+            }
+            _ => {
+                for option in s.split('+') {
+                    match option {
+                        "goal" => options.share_goal = true,
+                        "positions" => options.share_positions = true,
+                        "maze" => options.share_maze = true,
+                        "guiding" => options.agent_guiding = true,
+                        _ => return Err(format!("Unknown communication option '{}'", option)),
+                    }
+                }
+            }
+        }
+        Ok(options)
+    }
 }
 
 pub struct MamofHeadlessPlugin;
@@ -66,39 +192,131 @@ impl Plugin for MamofHeadlessPlugin {
         app.add_systems(Startup, launch_simulation);
         app.add_systems(
             Update,
-            on_complete.run_if(on_event::<AgentsCompleteMazeEvent>),
+            on_complete
+                .run_if(on_event::<AgentsCompleteMazeEvent>.or(on_event::<SimulationTimeoutEvent>)),
         );
     }
 }
 
 fn launch_simulation(
+    mut commands: Commands,
     mut settings: ResMut<SimulationSettings>,
     cli_args: Res<CliArgs>,
+    mut time: ResMut<Time<Virtual>>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
+    match &cli_args.config {
+        Some(path) => *settings = load_config_settings(path),
+        None => apply_cli_args(&mut settings, &cli_args),
+    }
+    settings.simulation_speed = cli_args.simulation_speed;
+    let maze_file = settings.maze_file.as_deref().map(Maze::load).transpose();
+    if let Err(e) = maze_file.and_then(|maze| settings.validate(maze.as_ref())) {
+        eprintln!("Invalid settings: {}", e);
+        std::process::exit(1);
+    }
+    if cli_args.unthrottled {
+        // advancing the clock by exactly one timestep per frame makes every
+        // app update run one simulation step, without sleeping in between, as
+        // long as the virtual clock does not clamp it to its maximum delta
+        let timestep = settings.simulation_speed.to_duration();
+        time.set_max_delta(timestep);
+        commands.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+    }
+    println!("{:#?}", settings.into_inner());
+    game_state.set(GameState::Simulation);
+}
+
+fn apply_cli_args(settings: &mut SimulationSettings, cli_args: &CliArgs) {
     settings.num_agents = cli_args.num_agents;
-    settings.grid_size = cli_args.map_size;
+    settings.grid_width = cli_args.maze.map_size.width;
+    settings.grid_height = cli_args.maze.map_size.height;
+    settings.maze_algorithm = cli_args.maze.maze_algorithm;
+    settings.wall_removal = cli_args.maze.wall_removal;
+    settings.rooms = cli_args.maze.rooms;
+    settings.maze_file = cli_args.maze_file.clone();
+    settings.num_goals = cli_args.maze.num_goals;
+    settings.mission = cli_args.mission;
+    settings.spawn_placement = cli_args.spawn_placement;
+    settings.spawn_goal_distance = cli_args.spawn_goal_distance;
+    settings.unknown_goals = cli_args.unknown_goals;
+    settings.strategy = cli_args.strategy;
+    settings.team = cli_args.team.clone();
+    settings.coordinated = cli_args.coordinated;
+    settings.sensor = Sensor {
+        radius: cli_args.sensor_radius,
+        line_of_sight: cli_args.line_of_sight,
+        diagonal: cli_args.diagonal_sensing,
+    };
     settings.share_goal = !cli_args.disable_goal_sharing;
     settings.share_positions = !cli_args.disable_position_sharing;
     settings.share_tiles = !cli_args.disable_map_sharing;
-    settings.simulation_speed = cli_args.simulation_speed;
+    settings.enable_guiding = !cli_args.disable_agent_guiding;
+    settings.max_timesteps = cli_args.max_timesteps;
     settings.map_seed = cli_args
         .seed
         .map_or(SeedType::Random, |seed| SeedType::Selected(seed));
-    println!("{:#?}", settings.into_inner());
-    game_state.set(GameState::Simulation);
+    settings.maze_seed = cli_args.maze_seed;
+    settings.spawn_seed = cli_args.spawn_seed;
+    settings.behaviour_seed = cli_args.behaviour_seed;
+}
+
+/// The settings of an experiment config describing exactly one run.
+fn load_config_settings(path: &str) -> SimulationSettings {
+    let experiment = ExperimentConfig::load(path).unwrap_or_else(|e| {
+        eprintln!("Invalid experiment config: {}", e);
+        std::process::exit(1);
+    });
+    let mut runs = experiment.create_settings();
+    if runs.len() != 1 {
+        eprintln!(
+            "Invalid experiment config: it describes {} runs, a single run needs exactly one agent count, map size, maze algorithm, communication preset and seed",
+            runs.len()
+        );
+        std::process::exit(1);
+    }
+    runs.remove(0)
 }
 
 fn on_complete(
     step: Res<SimulationStepData>,
     mut event: EventWriter<AppExit>,
     cli_args: Res<CliArgs>,
-    query: Query<&Agent>,
+    query: Query<(&Agent, &AgentMetrics)>,
     settings: Res<SimulationSettings>,
+    (goal_metrics, maze_analysis, maze): (Res<MazeGoalMetrics>, Res<MazeAnalysis>, Res<Maze>),
     rand: Res<GlobalRng>,
 ) {
-    println!("Simulation completed in {} timesteps", step.timesteps);
-    let summary = create_simulation_summary(rand.seed, &settings, &query, step.timesteps);
+    let agents = query.iter().collect::<Vec<(&Agent, &AgentMetrics)>>();
+    let summary = create_simulation_summary(
+        &rand,
+        &settings,
+        &maze,
+        &agents,
+        &goal_metrics.0,
+        &maze_analysis,
+        step.timesteps,
+    );
+    if summary.results.completed {
+        println!("Simulation completed in {} timesteps", step.timesteps);
+    } else if settings.mission == Mission::CollectAll {
+        let goals_reached = goal_metrics
+            .0
+            .iter()
+            .filter(|goal| goal.reached_at.is_some())
+            .count();
+        println!(
+            "Simulation stopped after {} timesteps with {}/{} goals visited",
+            step.timesteps,
+            goals_reached,
+            goal_metrics.0.len()
+        );
+    } else {
+        println!(
+            "Simulation stopped after {} timesteps with {}/{} agents on the goal",
+            step.timesteps, summary.results.agents_finished, settings.num_agents
+        );
+    }
     let contents =
         serde_json::to_string_pretty(&summary).expect("Failed to create simulation summary");
     if let Some(output_file) = &cli_args.output_file {
@@ -107,27 +325,135 @@ fn on_complete(
     event.write(AppExit::Success);
 }
 
+/// Runs without a timestep limit stop after this many timesteps per tile of the
+/// maze, far more than exploring it takes, so a stuck run can not block a batch.
+const DEFAULT_TIMESTEPS_PER_TILE: u64 = 100;
+
+/// Runs a whole simulation inside a fresh app, unthrottled, and returns its summary.
+pub fn run_simulation(settings: SimulationSettings) -> SimulationSummary {
+    let timestep = settings.simulation_speed.to_duration();
+    let mut app = App::new();
+    app.add_plugins(StatesPlugin);
+    app.insert_state(GameState::Simulation);
+    app.add_plugins((MinimalPlugins, MamofCorePlugins));
+    app.insert_resource(settings);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+    app.world_mut()
+        .resource_mut::<Time<Virtual>>()
+        .set_max_delta(timestep);
+    app.finish();
+    app.cleanup();
+    // the first update only enters the simulation and creates the maze
+    app.update();
+    let maze = app.world().resource::<Maze>();
+    let tiles = (maze.width() * maze.height()) as u64;
+    let mut settings = app.world_mut().resource_mut::<SimulationSettings>();
+    if settings.max_timesteps.is_none() {
+        settings.max_timesteps = Some(DEFAULT_TIMESTEPS_PER_TILE * tiles);
+    }
+    while !app.world().resource::<SimulationStepData>().stopped {
+        app.update();
+    }
+
+    let world = app.world_mut();
+    let agents = world
+        .query::<(&Agent, &AgentMetrics)>()
+        .iter(world)
+        .collect::<Vec<(&Agent, &AgentMetrics)>>();
+    create_simulation_summary(
+        world.resource::<GlobalRng>(),
+        world.resource::<SimulationSettings>(),
+        world.resource::<Maze>(),
+        &agents,
+        &world.resource::<MazeGoalMetrics>().0,
+        world.resource::<MazeAnalysis>(),
+        world.resource::<SimulationStepData>().timesteps,
+    )
+}
+
 fn create_simulation_summary(
-    seed: u64,
+    rng: &GlobalRng,
     settings: &SimulationSettings,
-    query: &Query<&Agent>,
+    maze: &Maze,
+    agents: &[(&Agent, &AgentMetrics)],
+    goal_metrics: &[GoalMetrics],
+    maze_analysis: &MazeAnalysis,
     timesteps: u64,
 ) -> SimulationSummary {
-    let union_set = query
+    let mut union_set = TileSet::default();
+    agents
         .iter()
-        .map(|a| a.explored.clone())
-        .reduce(|m1, m2| m1.union(&m2).map(|n| *n).collect());
-    let count = union_set.unwrap_or(HashSet::new()).len();
-    let mut agent_map = HashMap::new();
-    query.iter().for_each(|a| {
+        .for_each(|(a, _)| union_set.union_with(&a.explored));
+    let count = union_set.len();
+    let mut agent_map = BTreeMap::new();
+    let mut finish_map = BTreeMap::new();
+    let mut metrics_map = BTreeMap::new();
+    agents.iter().for_each(|(a, metrics)| {
         agent_map.insert(a.id, a.explored.len());
+        finish_map.insert(a.id, a.finished_at);
+        metrics_map.insert(a.id, (*metrics).clone());
     });
+    let agents_finished = agents
+        .iter()
+        .filter(|(a, _)| a.finished_at.is_some())
+        .count();
     SimulationSummary {
-        configuration: SimulationConfiguration::create_from(seed, settings),
+        configuration: SimulationConfiguration::create_from(rng, settings, maze),
         results: SimulationResults {
+            completed: agents_finished == agents.len(),
             timesteps: timesteps,
+            agents_finished,
+            agent_finish_timesteps: finish_map,
             total_unique_explored_titles: count,
             agent_explored_tiles: agent_map,
+            agent_metrics: metrics_map,
+            goal_metrics: goal_metrics.to_vec(),
+            maze_analysis: maze_analysis.clone(),
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(goal: bool, positions: bool, maze: bool, guiding: bool) -> CommunicationOptions {
+        CommunicationOptions {
+            share_goal: goal,
+            share_positions: positions,
+            share_maze: maze,
+            agent_guiding: guiding,
+        }
+    }
+
+    #[test]
+    fn parse_none_and_all() {
+        assert_eq!(
+            CommunicationOptions::parse("none"),
+            Ok(options(false, false, false, false))
+        );
+        assert_eq!(
+            CommunicationOptions::parse("all"),
+            Ok(options(true, true, true, true))
+        );
+    }
+
+    #[test]
+    fn parse_combination_in_any_order() {
+        assert_eq!(
+            CommunicationOptions::parse("maze+goal"),
+            Ok(options(true, false, true, false))
+        );
+        assert_eq!(
+            CommunicationOptions::parse("guiding+positions"),
+            Ok(options(false, true, false, true))
+        );
+    }
+
+    #[test]
+    fn parse_unknown_option() {
+        assert!(CommunicationOptions::parse("goal+tiles").is_err());
+        assert!(CommunicationOptions::parse("").is_err());
+        assert!(CommunicationOptions::parse("none+goal").is_err());
+    }
+}
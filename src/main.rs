@@ -1,27 +1,40 @@
 use agent::AgentPlugin;
 use bevy::{app::PluginGroupBuilder, log::LogPlugin, prelude::*, state::app::StatesPlugin};
 use clap::Parser;
-use cli_args::CliArgs;
+use cli_args::{CliArgs, Command};
 use controls::{
     camera_controller::CameraControllerPlugin, simulation_controller::SimulationControllerPlugin,
+    timeline_controller::TimelineControllerPlugin,
 };
 use headless::MamofHeadlessPlugin;
-use maze::MazePlugin;
+use maze::{Maze, MazePlugin};
+use metrics::MetricsPlugin;
 use render::{agent_render::AgentRenderPlugin, maze_render::MazeRenderPlugin};
+use replay::{Replay, ReplayPlugin, ReplayRecorder};
 use rng::RngPlugin;
-use settings::SettingsPlugin;
+use settings::{Sensor, SettingsPlugin, SimulationSettings};
 use simulation::SimulationPlugin;
+use trace::{TracePlugin, TraceWriter};
 use ui::{main_menu::MainMenuPlugin, simulation::SimulationUiPlugin};
 
 mod agent;
+mod analysis;
+mod batch;
 mod cli_args;
 mod controls;
+mod experiment;
+mod export;
 mod headless;
 mod maze;
+mod metrics;
 mod render;
+mod replay;
 mod rng;
 mod settings;
 mod simulation;
+mod statistics;
+mod strategy;
+mod trace;
 mod ui;
 mod utils;
 
@@ -34,12 +47,60 @@ pub enum GameState {
 
 fn main() {
     let args = CliArgs::parse();
+    match &args.command {
+        Some(Command::Batch(batch_args)) => {
+            batch::run_batch(batch_args);
+            return;
+        }
+        Some(Command::ExportMaze(export_args)) => {
+            export::run_export_maze(export_args);
+            return;
+        }
+        Some(Command::Analyze(analyze_args)) => {
+            analysis::run_analyze(analyze_args);
+            return;
+        }
+        None => {}
+    }
 
     let mut app = App::new();
     app.add_plugins(StatesPlugin);
     app.insert_state(GameState::InMenu);
     app.add_plugins(MamofCorePlugins);
     app.insert_resource(args.clone());
+    // the menu has no way to pick a team or the sensor, so they come from the arguments
+    let mut settings = app.world_mut().resource_mut::<SimulationSettings>();
+    settings.team.clone_from(&args.team);
+    settings.sensor = Sensor {
+        radius: args.sensor_radius,
+        line_of_sight: args.line_of_sight,
+        diagonal: args.diagonal_sensing,
+    };
+    if let Some(trace_file) = &args.trace {
+        match TraceWriter::create(trace_file) {
+            Ok(trace) => app.insert_resource(trace),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+    }
+    if let Some(record_file) = &args.record {
+        app.insert_resource(ReplayRecorder::new(record_file));
+    }
+    if let Some(Err(e)) = args.maze_file.as_deref().map(Maze::load) {
+        eprintln!("Invalid maze file: {}", e);
+        std::process::exit(1);
+    }
+    if let Some(replay_file) = &args.replay {
+        match Replay::load(replay_file) {
+            Ok(replay) => app.insert_resource(replay),
+            Err(e) => {
+                eprintln!("Invalid replay file: {}", e);
+                std::process::exit(1);
+            }
+        };
+    }
 
     if args.headless {
         println!("Running in HEADLESS mode");
@@ -78,6 +139,9 @@ impl PluginGroup for MamofCorePlugins {
             .add(SimulationPlugin)
             .add(MazePlugin)
             .add(AgentPlugin)
+            .add(MetricsPlugin)
+            .add(TracePlugin)
+            .add(ReplayPlugin)
     }
 }
 
@@ -88,6 +152,7 @@ impl PluginGroup for MamofRenderPlugins {
         PluginGroupBuilder::start::<Self>()
             .add(CameraControllerPlugin)
             .add(SimulationControllerPlugin)
+            .add(TimelineControllerPlugin)
             .add(MainMenuPlugin)
             .add(SimulationUiPlugin)
             .add(MazeRenderPlugin)
@@ -1,10 +1,18 @@
+use std::fs;
+
 use bevy::prelude::*;
-use knossos::maze::{GameMap, GrowingTree, Method, OrthogonalMazeBuilder};
+use knossos::maze::{
+    AldousBroder, Algorithm, Bias, BinaryTree, Eller, GameMap, GrowingTree, HuntAndKill, Kruskal,
+    Method, OrthogonalMazeBuilder, Prim, RecursiveBacktracking, Sidewinder,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
 
 use crate::{
     GameState,
+    replay::Replay,
     rng::{GlobalRng, seed_rng},
-    settings::SimulationSettings,
+    settings::{MazeAlgorithm, SimulationSettings, SpawnPlacement},
+    utils::grid::{TileGrid, TileSet},
 };
 
 #[derive(Default, PartialEq, Eq, Debug, Clone, Copy)]
@@ -17,8 +25,226 @@ pub enum Tile {
 
 #[derive(Resource, Default)]
 pub struct Maze {
-    pub tile_grid: Vec<Vec<Tile>>,
-    pub goal: (usize, usize),
+    pub tile_grid: TileGrid,
+    pub goals: Vec<(usize, usize)>,
+    pub starts: Vec<(usize, usize)>,
+}
+
+impl Maze {
+    /// Loads a hand-authored map, in the same format as [`Maze::from_ascii`].
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read maze file '{}': {}", path, e))?;
+        Self::parse(&contents)
+    }
+
+    /// Reads a maze in the text format of `load`, checking that it can be run.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let map = contents.trim_end();
+        let maze = Self::from_ascii(map);
+        let width = maze.width();
+        if width < 3 || maze.height() < 3 || map.lines().any(|line| line.chars().count() != width) {
+            return Err(String::from(
+                "Maze needs to be a rectangle of at least 3 by 3 tiles",
+            ));
+        }
+        if maze.goals.is_empty() {
+            return Err(String::from("Maze needs at least one goal 'G'"));
+        }
+        if let Some((x, y)) = maze.find_unreachable_tile() {
+            return Err(format!("Tile ({}, {}) can not reach every goal", x, y));
+        }
+        Ok(maze)
+    }
+
+    pub fn width(&self) -> usize {
+        self.tile_grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.tile_grid.height()
+    }
+
+    pub fn is_goal(&self, position: (usize, usize)) -> bool {
+        self.goals.contains(&position)
+    }
+
+    pub fn floor_positions(&self) -> Vec<(usize, usize)> {
+        self.tile_grid
+            .positions()
+            .filter(|(_, tile)| *tile == Tile::Floor)
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// Places the goals on random border tiles next to the floor and returns the
+    /// start tile. The start and first goal are placed like the knossos `GameMap`
+    /// formatter does for square mazes, which mixes up rows and columns for the
+    /// other ones.
+    fn place_goals(&mut self, seed: u64, count: u32) -> Result<(usize, usize), String> {
+        let (width, height) = (self.width(), self.height());
+        let mut positions = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if !(y == 0 || y == height - 1 || x == 0 || x == width - 1) {
+                    continue;
+                }
+                let neighbors = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                let next_to_floor = neighbors
+                    .iter()
+                    .any(|neighbor| self.tile_grid.get(*neighbor) == Some(Tile::Floor));
+                if next_to_floor {
+                    positions.push((x, y));
+                }
+            }
+        }
+        positions.shuffle(&mut StdRng::seed_from_u64(seed));
+        if positions.is_empty() {
+            return Err(String::from("No border tile is next to the floor"));
+        }
+        // knossos places the start first and the goal on another row and column
+        let (start_x, start_y) = positions.remove(0);
+        let first = positions
+            .iter()
+            .position(|(x, y)| *x != start_x && *y != start_y)
+            .ok_or("No border tile is left for a goal")?;
+        let first_goal = positions.remove(first);
+        positions.insert(0, first_goal);
+        if positions.len() < count as usize {
+            return Err(format!(
+                "Only {} of the {} goals fit on the border of the maze",
+                positions.len(),
+                count
+            ));
+        }
+        positions.iter().take(count as usize).for_each(|(x, y)| {
+            self.tile_grid[(*x, *y)] = Tile::Goal;
+            self.goals.push((*x, *y));
+        });
+        Ok((start_x, start_y))
+    }
+
+    /// Finds a tile that can not reach the first goal, so not every goal either.
+    fn find_unreachable_tile(&self) -> Option<(usize, usize)> {
+        let mut reached = TileSet::new(self.width(), self.height());
+        reached.insert(self.goals[0]);
+        let mut stack = vec![self.goals[0]];
+        while let Some((x, y)) = stack.pop() {
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for (nx, ny) in neighbors {
+                let open = self
+                    .tile_grid
+                    .get((nx, ny))
+                    .is_some_and(|tile| tile != Tile::Wall);
+                if open && reached.insert((nx, ny)) {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        self.tile_grid
+            .positions()
+            .find(|(position, tile)| *tile != Tile::Wall && !reached.contains(position))
+            .map(|(position, _)| position)
+    }
+
+    /// Parses a map where `#` is a wall, `G` a goal, `S` an optional agent
+    /// start position and anything else is floor.
+    pub fn from_ascii(map: &str) -> Self {
+        let mut maze = Maze::default();
+        let mut rows = Vec::new();
+        for (y, line) in map.lines().enumerate() {
+            let mut vec = Vec::with_capacity(line.len());
+            for (x, c) in line.chars().enumerate() {
+                if c == '#' {
+                    vec.push(Tile::Wall);
+                } else if c == 'G' {
+                    vec.push(Tile::Goal);
+                    maze.goals.push((x, y));
+                } else if c == 'S' {
+                    vec.push(Tile::Floor);
+                    maze.starts.push((x, y));
+                } else {
+                    vec.push(Tile::Floor);
+                }
+            }
+            rows.push(vec);
+        }
+        maze.tile_grid = TileGrid::from_rows(rows);
+        maze
+    }
+
+    /// Knocks down the given fraction of the walls between two floor tiles, creating loops.
+    pub fn remove_walls(&mut self, fraction: f64, rng: &mut StdRng) {
+        let (width, height) = (self.width(), self.height());
+        let is_open = |maze: &Maze, x: usize, y: usize| maze.tile_grid[(x, y)] != Tile::Wall;
+        let mut walls = Vec::new();
+        for y in 1..height.saturating_sub(1) {
+            for x in 1..width.saturating_sub(1) {
+                if self.tile_grid[(x, y)] != Tile::Wall {
+                    continue;
+                }
+                let horizontal = is_open(self, x - 1, y) && is_open(self, x + 1, y);
+                let vertical = is_open(self, x, y - 1) && is_open(self, x, y + 1);
+                if horizontal != vertical {
+                    walls.push((x, y));
+                }
+            }
+        }
+        walls.shuffle(rng);
+        let count = (walls.len() as f64 * fraction.clamp(0.0, 1.0)).round() as usize;
+        walls.iter().take(count).for_each(|(x, y)| {
+            self.tile_grid[(*x, *y)] = Tile::Floor;
+        });
+    }
+
+    /// Clears rectangular rooms of 3 to 7 tiles per side at random places inside the border.
+    pub fn carve_rooms(&mut self, count: u32, rng: &mut StdRng) {
+        let (width, height) = (self.width(), self.height());
+        if width < 5 || height < 5 {
+            return;
+        }
+        for _ in 0..count {
+            let room_width = rng.random_range(3..=7.min(width - 2));
+            let room_height = rng.random_range(3..=7.min(height - 2));
+            let x0 = rng.random_range(1..=width - 1 - room_width);
+            let y0 = rng.random_range(1..=height - 1 - room_height);
+            for y in y0..y0 + room_height {
+                for x in x0..x0 + room_width {
+                    if self.tile_grid[(x, y)] == Tile::Wall {
+                        self.tile_grid[(x, y)] = Tile::Floor;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn to_ascii(&self) -> Vec<String> {
+        self.tile_grid
+            .rows()
+            .enumerate()
+            .map(|(y, line)| {
+                line.iter()
+                    .enumerate()
+                    .map(|(x, tile)| match tile {
+                        Tile::Wall => '#',
+                        Tile::Floor if self.starts.contains(&(x, y)) => 'S',
+                        Tile::Floor => '.',
+                        Tile::Goal => 'G',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
 }
 
 pub struct MazePlugin;
@@ -26,42 +252,75 @@ pub struct MazePlugin;
 impl Plugin for MazePlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Maze::default());
-        app.add_systems(OnEnter(GameState::Simulation), create_maze.after(seed_rng));
+        app.add_systems(
+            OnEnter(GameState::Simulation),
+            create_maze
+                .after(seed_rng)
+                .run_if(not(resource_exists::<Replay>)),
+        );
     }
 }
 
 pub fn create_maze(
     mut maze: ResMut<Maze>,
-    rand: ResMut<GlobalRng>,
+    rand: Res<GlobalRng>,
     settings: Res<SimulationSettings>,
 ) {
+    if let Some(path) = &settings.maze_file {
+        *maze = Maze::load(path).expect("Failed to load maze file");
+        return;
+    }
+    let mut rng = StdRng::seed_from_u64(rand.maze_seed);
+    *maze = generate_maze(&settings, rand.maze_seed, &mut rng).unwrap_or_else(|e| {
+        eprintln!("Failed to generate the maze: {}", e);
+        std::process::exit(1);
+    });
+}
+
+/// Builds the maze of the given seed, the rng is only used by the post-processing
+/// and seeded with the same seed by the simulation.
+pub fn generate_maze(
+    settings: &SimulationSettings,
+    seed: u64,
+    rng: &mut StdRng,
+) -> Result<Maze, String> {
     let generated_maze = OrthogonalMazeBuilder::new()
-        .width(settings.grid_size)
-        .height(settings.grid_size)
-        .seed(Some(rand.seed))
-        .algorithm(Box::new(GrowingTree::new(Method::Random)))
+        .width(settings.grid_width)
+        .height(settings.grid_height)
+        .seed(Some(seed))
+        .algorithm(create_algorithm(settings.maze_algorithm))
         .build();
-    let formatter = GameMap::new()
-        .wall('#')
-        .span(1)
-        .with_start_goal()
-        .seed(Some(rand.seed))
-        .goal('G')
-        .start('#');
+    let formatter = GameMap::new().wall('#').passage('.').span(1);
     let game_map = generated_maze.format(formatter).into_inner();
-    maze.tile_grid = Vec::new();
-    for (y, line) in game_map.lines().enumerate() {
-        let mut vec = Vec::with_capacity(line.len());
-        for (x, c) in line.chars().enumerate() {
-            if c == '#' {
-                vec.push(Tile::Wall);
-            } else if c == 'G' {
-                vec.push(Tile::Goal);
-                maze.goal = (x, y);
-            } else {
-                vec.push(Tile::Floor);
-            }
-        }
-        maze.tile_grid.push(vec);
+    let mut maze = Maze::from_ascii(&game_map);
+    let start = maze.place_goals(seed, settings.num_goals)?;
+    // the start is only opened when used, so the other mazes stay the same
+    if settings.spawn_placement == SpawnPlacement::Start {
+        maze.tile_grid[start] = Tile::Floor;
+        maze.starts.push(start);
+    }
+    if settings.wall_removal > 0.0 {
+        maze.remove_walls(settings.wall_removal, rng);
+    }
+    if settings.rooms > 0 {
+        maze.carve_rooms(settings.rooms, rng);
+    }
+    Ok(maze)
+}
+
+fn create_algorithm(algorithm: MazeAlgorithm) -> Box<dyn Algorithm> {
+    match algorithm {
+        MazeAlgorithm::GrowingTreeRandom => Box::new(GrowingTree::new(Method::Random)),
+        MazeAlgorithm::GrowingTreeNewest => Box::new(GrowingTree::new(Method::Newest)),
+        MazeAlgorithm::GrowingTreeOldest => Box::new(GrowingTree::new(Method::Oldest)),
+        MazeAlgorithm::GrowingTreeMiddle => Box::new(GrowingTree::new(Method::Middle)),
+        MazeAlgorithm::RecursiveBacktracking => Box::new(RecursiveBacktracking),
+        MazeAlgorithm::Kruskal => Box::new(Kruskal),
+        MazeAlgorithm::Prim => Box::new(Prim::new()),
+        MazeAlgorithm::Eller => Box::new(Eller),
+        MazeAlgorithm::BinaryTree => Box::new(BinaryTree::new(Bias::NorthWest)),
+        MazeAlgorithm::Sidewinder => Box::new(Sidewinder),
+        MazeAlgorithm::AldousBroder => Box::new(AldousBroder),
+        MazeAlgorithm::HuntAndKill => Box::new(HuntAndKill::new()),
     }
 }
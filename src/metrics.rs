@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GameState,
+    agent::{self, Agent},
+    analysis::{self, MazeAnalysis},
+    maze::Maze,
+    settings::{Role, Strategy},
+    simulation::{SimulationStepData, SimulationStepEvent},
+    utils::{grid::TileSet, heuristics},
+};
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MazeGoalMetrics::default());
+        app.insert_resource(MazeAnalysis::default());
+        app.add_systems(
+            OnEnter(GameState::Simulation),
+            (
+                attach_agent_metrics.after(agent::spawn_agents),
+                reset_goal_metrics.after(agent::spawn_agents),
+                analysis::analyze_maze.after(agent::spawn_agents),
+            ),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (update_agent_metrics, update_goal_metrics)
+                .after(agent::move_agent_path)
+                .run_if(on_event::<SimulationStepEvent>),
+        );
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Default, Clone, Debug)]
+pub struct AgentMetrics {
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub strategy: Strategy,
+    pub goal_seen_at: Option<u64>,
+    pub goal_reached_at: Option<u64>,
+    pub distance_travelled: u64,
+    pub revisited_tiles: u64,
+    pub guiding_timesteps: u64,
+    #[serde(skip)]
+    last_position: (usize, usize),
+    #[serde(skip)]
+    visited: TileSet,
+}
+
+impl AgentMetrics {
+    fn new(agent: &Agent, maze: &Maze) -> Self {
+        let mut visited = TileSet::new(maze.width(), maze.height());
+        visited.insert(agent.position);
+        Self {
+            role: agent.role,
+            strategy: agent.strategy,
+            last_position: agent.position,
+            visited,
+            ..default()
+        }
+    }
+}
+
+/// When a goal of the maze was first seen and first reached, and by which agent.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct GoalMetrics {
+    pub position: (usize, usize),
+    pub seen_at: Option<u64>,
+    pub seen_by: Option<usize>,
+    pub reached_at: Option<u64>,
+    pub reached_by: Option<usize>,
+}
+
+impl GoalMetrics {
+    fn new(position: (usize, usize)) -> Self {
+        Self {
+            position,
+            ..default()
+        }
+    }
+}
+
+#[derive(Resource, Default, Clone)]
+pub struct MazeGoalMetrics(pub Vec<GoalMetrics>);
+
+pub fn attach_agent_metrics(
+    mut commands: Commands,
+    query: Query<(Entity, &Agent)>,
+    maze: Res<Maze>,
+) {
+    query.iter().for_each(|(entity, agent)| {
+        commands
+            .entity(entity)
+            .insert(AgentMetrics::new(agent, &maze));
+    });
+}
+
+pub fn reset_goal_metrics(mut goal_metrics: ResMut<MazeGoalMetrics>, maze: Res<Maze>) {
+    goal_metrics.0 = maze
+        .goals
+        .iter()
+        .map(|goal| GoalMetrics::new(*goal))
+        .collect();
+}
+
+fn update_agent_metrics(
+    mut query: Query<(&Agent, &mut AgentMetrics)>,
+    maze: Res<Maze>,
+    step: Res<SimulationStepData>,
+) {
+    query.iter_mut().for_each(|(agent, mut metrics)| {
+        let goal_seen =
+            maze.is_goal(agent.position) || maze.goals.iter().any(|goal| agent.seen.contains(goal));
+        if metrics.goal_seen_at.is_none() && goal_seen {
+            metrics.goal_seen_at = Some(step.timesteps);
+        }
+        if metrics.goal_reached_at.is_none() && maze.is_goal(agent.position) {
+            metrics.goal_reached_at = Some(step.timesteps);
+        }
+        if !maze.is_goal(agent.position)
+            && agent.found_goal.is_some()
+            && !maze.is_goal(agent.current_goal)
+        {
+            metrics.guiding_timesteps += 1;
+        }
+        if agent.position != metrics.last_position {
+            let distance = heuristics::manhattan(agent.position, metrics.last_position);
+            metrics.distance_travelled += distance as u64;
+            if !metrics.visited.insert(agent.position) {
+                metrics.revisited_tiles += 1;
+            }
+            metrics.last_position = agent.position;
+        }
+    });
+}
+
+fn update_goal_metrics(
+    query: Query<&Agent>,
+    mut goal_metrics: ResMut<MazeGoalMetrics>,
+    step: Res<SimulationStepData>,
+) {
+    // the agent with the lowest id gets the credit when several get there together
+    let mut agents = query.iter().collect::<Vec<&Agent>>();
+    agents.sort_by_key(|agent| agent.id);
+    goal_metrics.0.iter_mut().for_each(|goal| {
+        let seen_by = agents
+            .iter()
+            .find(|agent| agent.position == goal.position || agent.seen.contains(&goal.position));
+        if let (None, Some(agent)) = (goal.seen_at, seen_by) {
+            goal.seen_at = Some(step.timesteps);
+            goal.seen_by = Some(agent.id);
+        }
+        let reached_by = agents.iter().find(|agent| agent.position == goal.position);
+        if let (None, Some(agent)) = (goal.reached_at, reached_by) {
+            goal.reached_at = Some(step.timesteps);
+            goal.reached_by = Some(agent.id);
+        }
+    });
+}
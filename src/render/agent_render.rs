@@ -3,6 +3,7 @@ use bevy::prelude::*;
 use crate::{
     GameState,
     agent::{self, Agent},
+    settings::Role,
 };
 
 use super::maze_render::{TILE_GAP, TILE_SIZE};
@@ -52,7 +53,13 @@ fn setup_agent_sprites(
 ) {
     query.iter().for_each(|(entity, agent)| {
         let id = agent.id;
-        let mesh = meshes.add(Mesh::from(Circle::new((TILE_SIZE / 2) as f32)));
+        // followers are squares, so the mixed teams can be told apart
+        let mesh = match agent.role {
+            Role::Scout => meshes.add(Mesh::from(Circle::new((TILE_SIZE / 2) as f32))),
+            Role::Follower => {
+                meshes.add(Mesh::from(Rectangle::from_length(TILE_SIZE as f32 * 0.9)))
+            }
+        };
         let material = materials.add(ColorMaterial::from(AGENT_COLORS[id % AGENT_COLORS.len()]));
 
         commands
@@ -24,9 +24,9 @@ impl Plugin for MazeRenderPlugin {
 }
 
 fn spawn_maze_tile_sprites(mut commands: Commands, maze: Res<Maze>) {
-    for y in 0..maze.tile_grid.len() {
-        for x in 0..maze.tile_grid.len() {
-            let color = match maze.tile_grid[y][x] {
+    for y in 0..maze.height() {
+        for x in 0..maze.width() {
+            let color = match maze.tile_grid[(x, y)] {
                 Tile::Wall => Color::BLACK,
                 Tile::Floor => Color::WHITE,
                 Tile::Goal => Color::linear_rgb(1.0, 1.0, 0.0),
@@ -0,0 +1,250 @@
+use std::fs;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CliArgs, GameState,
+    agent::{self, Agent, SharedMazeKnowledge},
+    headless::SimulationConfiguration,
+    maze::{self, Maze, Tile},
+    rng::GlobalRng,
+    settings::{Mission, SeedType, SimulationSettings},
+    simulation::{self, SimulationStepData, SimulationStepEvent},
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, launch_replay.run_if(resource_exists::<Replay>));
+        app.add_systems(
+            OnEnter(GameState::Simulation),
+            (
+                load_replay_maze.before(maze::create_maze),
+                spawn_replay_agents
+                    .after(load_replay_maze)
+                    .before(agent::spawn_agents),
+            )
+                .run_if(resource_exists::<Replay>),
+        );
+        app.add_systems(
+            OnEnter(GameState::Simulation),
+            start_recording
+                .after(agent::spawn_agents)
+                .run_if(resource_exists::<ReplayRecorder>),
+        );
+        app.add_systems(OnExit(GameState::Simulation), stop_replay);
+        app.add_systems(
+            FixedUpdate,
+            (
+                play_replay
+                    .after(simulation::tick_simulation)
+                    .run_if(resource_exists::<Replay>),
+                record_step
+                    .after(agent::move_agent_path)
+                    .run_if(resource_exists::<ReplayRecorder>),
+            )
+                .run_if(on_event::<SimulationStepEvent>),
+        );
+        app.add_systems(
+            FixedPostUpdate,
+            save_recording
+                .after(simulation::check_timestep_limit)
+                .run_if(resource_exists::<ReplayRecorder>.and(on_event::<SimulationStepEvent>)),
+        );
+    }
+}
+
+/// A recorded run, self-contained so it can be played back after the maze
+/// generation or the agent behaviour changed.
+#[derive(Resource, Serialize, Deserialize)]
+pub struct Replay {
+    pub configuration: SimulationConfiguration,
+    pub maze: Vec<String>,
+    pub agents: Vec<(usize, usize)>,
+    pub steps: Vec<Vec<(usize, usize)>>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read replay file '{}': {}", path, e))?;
+        let replay: Self = serde_json::from_str(&contents).map_err(|e| format!("{}", e))?;
+        let maze = Maze::parse(&replay.maze.join("\n"))
+            .map_err(|e| format!("Replay has an invalid maze: {}", e))?;
+        if replay
+            .steps
+            .iter()
+            .any(|positions| positions.len() != replay.agents.len())
+        {
+            return Err(String::from(
+                "Replay has steps with a wrong number of agents",
+            ));
+        }
+        let on_wall = |position: &(usize, usize)| {
+            maze.tile_grid
+                .get(*position)
+                .is_none_or(|tile| tile == Tile::Wall)
+        };
+        if replay
+            .agents
+            .iter()
+            .chain(replay.steps.iter().flatten())
+            .any(on_wall)
+        {
+            return Err(String::from(
+                "Replay has agents outside of the maze or inside a wall",
+            ));
+        }
+        Ok(replay)
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    path: String,
+    replay: Option<Replay>,
+}
+
+impl ReplayRecorder {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            replay: None,
+        }
+    }
+
+    /// Drops the steps after the timestep, which get recorded again once the
+    /// simulation continues from it.
+    pub fn rewind(&mut self, timestep: u64) {
+        if let Some(replay) = &mut self.replay {
+            replay.steps.truncate(timestep as usize);
+        }
+    }
+}
+
+fn launch_replay(
+    replay: Res<Replay>,
+    cli_args: Res<CliArgs>,
+    mut settings: ResMut<SimulationSettings>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let configuration = &replay.configuration;
+    settings.num_agents = configuration.num_agents;
+    settings.grid_width = configuration.map_width;
+    settings.grid_height = configuration.map_height;
+    settings.maze_algorithm = configuration.maze_algorithm;
+    settings.wall_removal = configuration.wall_removal;
+    settings.rooms = configuration.rooms;
+    settings.num_goals = configuration.num_goals;
+    settings.mission = configuration.mission;
+    settings.spawn_placement = configuration.spawn_placement;
+    settings.spawn_goal_distance = configuration.spawn_goal_distance;
+    settings.unknown_goals = configuration.unknown_goals;
+    settings.strategy = configuration.strategy;
+    settings.team = configuration.team.clone();
+    settings.coordinated = configuration.coordinated;
+    settings.sensor = configuration.sensor;
+    settings.map_seed = SeedType::Selected(configuration.seed);
+    settings.simulation_speed = cli_args.simulation_speed;
+    configuration.communication_options.apply_to(&mut settings);
+    game_state.set(GameState::Simulation);
+}
+
+fn load_replay_maze(mut maze: ResMut<Maze>, replay: Res<Replay>) {
+    *maze = Maze::from_ascii(&replay.maze.join("\n"));
+}
+
+fn spawn_replay_agents(
+    mut commands: Commands,
+    maze: Res<Maze>,
+    replay: Res<Replay>,
+    settings: Res<SimulationSettings>,
+) {
+    replay.agents.iter().enumerate().for_each(|(id, position)| {
+        let mut agent = Agent::new(id, *position, &maze);
+        (agent.role, agent.strategy) = settings.team_member(id);
+        commands.spawn(agent);
+    });
+}
+
+pub fn play_replay(
+    replay: Res<Replay>,
+    mut query: Query<&mut Agent>,
+    mut knowledge: ResMut<SharedMazeKnowledge>,
+    mut step: ResMut<SimulationStepData>,
+    maze: Res<Maze>,
+    settings: Res<SimulationSettings>,
+) {
+    let Some(positions) = replay.steps.get(step.timesteps.saturating_sub(1) as usize) else {
+        step.stop();
+        return;
+    };
+    query.iter_mut().for_each(|mut agent| {
+        agent.position = positions[agent.id];
+    });
+    match settings.mission {
+        Mission::AnyExit => {
+            knowledge.remaining_agents =
+                positions.iter().filter(|p| !maze.is_goal(**p)).count() as u32;
+        }
+        Mission::CollectAll => {
+            positions
+                .iter()
+                .filter(|p| maze.is_goal(**p))
+                .for_each(|p| {
+                    knowledge.visited_goals.insert(*p);
+                });
+            if knowledge.visited_goals.len() == maze.goals.len() {
+                knowledge.remaining_agents = 0;
+            }
+        }
+    }
+    if step.timesteps as usize >= replay.steps.len() {
+        step.stop();
+    }
+}
+
+fn stop_replay(mut commands: Commands) {
+    commands.remove_resource::<Replay>();
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    query: Query<&Agent>,
+    maze: Res<Maze>,
+    settings: Res<SimulationSettings>,
+    rand: Res<GlobalRng>,
+) {
+    let mut agents = query.iter().collect::<Vec<&Agent>>();
+    agents.sort_by_key(|agent| agent.id);
+    recorder.replay = Some(Replay {
+        configuration: SimulationConfiguration::create_from(&rand, &settings, &maze),
+        maze: maze.to_ascii(),
+        agents: agents.iter().map(|agent| agent.position).collect(),
+        steps: Vec::new(),
+    });
+}
+
+fn record_step(mut recorder: ResMut<ReplayRecorder>, query: Query<&Agent>) {
+    if let Some(replay) = &mut recorder.replay {
+        let mut positions = vec![(0, 0); replay.agents.len()];
+        query
+            .iter()
+            .for_each(|agent| positions[agent.id] = agent.position);
+        replay.steps.push(positions);
+    }
+}
+
+fn save_recording(recorder: Res<ReplayRecorder>, step: Res<SimulationStepData>) {
+    if !step.stopped {
+        return;
+    }
+    // the recording is kept, the run can be rewound and saved again
+    if let Some(replay) = &recorder.replay {
+        let contents = serde_json::to_string(replay).expect("Failed to create replay");
+        fs::write(&recorder.path, contents).expect("Failed to write to file!");
+        println!("Replay saved to {}", recorder.path);
+    }
+}
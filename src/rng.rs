@@ -8,22 +8,46 @@ use crate::{
 
 pub struct RngPlugin;
 
+/// The master seed of a simulation and the seeds derived from it. The maze and
+/// the spawn positions are created from their own seeds, `rng` is left for the
+/// agent behaviour.
 #[derive(Resource)]
 pub struct GlobalRng {
     pub rng: StdRng,
     pub seed: u64,
+    pub maze_seed: u64,
+    pub spawn_seed: u64,
+    pub behaviour_seed: u64,
 }
 
 impl Default for GlobalRng {
     fn default() -> Self {
-        let seed = gen_random_seed();
+        Self::new(gen_random_seed(), &SimulationSettings::default())
+    }
+}
+
+impl GlobalRng {
+    /// Derives the seeds that are not set in the settings from the master seed.
+    pub fn new(seed: u64, settings: &SimulationSettings) -> Self {
+        let behaviour_seed = settings
+            .behaviour_seed
+            .unwrap_or_else(|| derive_seed(seed, BEHAVIOUR_STREAM));
         Self {
-            rng: StdRng::seed_from_u64(seed),
+            rng: StdRng::seed_from_u64(behaviour_seed),
             seed,
+            // the maze keeps the master seed, so it matches `export-maze` with the same seed
+            maze_seed: settings.maze_seed.unwrap_or(seed),
+            spawn_seed: settings
+                .spawn_seed
+                .unwrap_or_else(|| derive_seed(seed, SPAWN_STREAM)),
+            behaviour_seed,
         }
     }
 }
 
+const SPAWN_STREAM: u64 = 1;
+const BEHAVIOUR_STREAM: u64 = 2;
+
 impl Plugin for RngPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(GlobalRng::default());
@@ -40,8 +64,16 @@ pub fn seed_rng(mut rng: ResMut<GlobalRng>, settings: Res<SimulationSettings>) {
         }
         SeedType::Selected(seed) => seed,
     };
-    rng.rng = StdRng::seed_from_u64(seed);
-    rng.seed = seed;
+    *rng = GlobalRng::new(seed, &settings);
+}
+
+/// Mixes the stream number into the seed with splitmix64, so every stream gets
+/// an unrelated seed.
+fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 fn gen_random_seed() -> u64 {
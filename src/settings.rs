@@ -1,6 +1,10 @@
-use std::time::Duration;
+use std::{fmt, time::Duration};
 
 use bevy::prelude::*;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{cli_args, maze::Maze};
 
 pub struct SettingsPlugin;
 
@@ -13,30 +17,411 @@ impl Plugin for SettingsPlugin {
 #[derive(Resource, Debug)]
 pub struct SimulationSettings {
     pub map_seed: SeedType,
+    pub maze_seed: Option<u64>,
+    pub spawn_seed: Option<u64>,
+    pub behaviour_seed: Option<u64>,
     pub num_agents: u32,
-    pub grid_size: usize,
+    pub grid_width: usize,
+    pub grid_height: usize,
+    pub maze_algorithm: MazeAlgorithm,
+    pub wall_removal: f64,
+    pub rooms: u32,
+    pub maze_file: Option<String>,
+    pub num_goals: u32,
+    pub mission: Mission,
+    pub spawn_placement: SpawnPlacement,
+    pub spawn_goal_distance: usize,
+    /// The agents only know the goals they saw or that were shared with them
+    pub unknown_goals: bool,
+    pub strategy: Strategy,
+    /// Groups of agents with their own role and strategy, the others are scouts
+    pub team: Vec<TeamGroup>,
+    /// Exploring agents get their frontier tiles allocated together, when sharing the maze
+    pub coordinated: bool,
+    pub sensor: Sensor,
     pub simulation_speed: SimulationSpeed,
     pub share_goal: bool,
     pub share_positions: bool,
     pub share_tiles: bool,
     pub enable_guiding: bool,
+    pub max_timesteps: Option<u64>,
 }
 
 impl Default for SimulationSettings {
     fn default() -> Self {
         Self {
             map_seed: SeedType::Random,
+            maze_seed: None,
+            spawn_seed: None,
+            behaviour_seed: None,
             num_agents: 2,
-            grid_size: 8,
+            grid_width: 8,
+            grid_height: 8,
+            maze_algorithm: MazeAlgorithm::GrowingTreeRandom,
+            wall_removal: 0.0,
+            rooms: 0,
+            maze_file: None,
+            num_goals: 1,
+            mission: Mission::AnyExit,
+            spawn_placement: SpawnPlacement::Random,
+            spawn_goal_distance: 8,
+            unknown_goals: false,
+            strategy: Strategy::Heuristic,
+            team: Vec::new(),
+            coordinated: false,
+            sensor: Sensor::default(),
             simulation_speed: SimulationSpeed::X1,
             share_goal: true,
             share_positions: true,
             share_tiles: true,
             enable_guiding: true,
+            max_timesteps: None,
+        }
+    }
+}
+
+impl SimulationSettings {
+    /// The role and strategy of an agent, the agents after the team groups are
+    /// scouts with the strategy of the simulation.
+    pub fn team_member(&self, id: usize) -> (Role, Strategy) {
+        let mut first = 0;
+        for group in &self.team {
+            first += group.count as usize;
+            if id < first {
+                return (group.role, group.strategy.unwrap_or(self.strategy));
+            }
+        }
+        (Role::Scout, self.strategy)
+    }
+
+    /// Checks the settings that depend on each other, the single values are
+    /// checked when they are parsed. `maze_file` is the loaded maze file of the
+    /// settings, if they have one.
+    pub fn validate(&self, maze_file: Option<&Maze>) -> Result<(), String> {
+        let seeded = matches!(self.map_seed, SeedType::Selected(_)) || self.maze_seed.is_some();
+        if seeded && self.maze_file.is_none() {
+            cli_args::validate_seeded_algorithm(self.maze_algorithm)?;
+        }
+        let team_size = self.team.iter().map(|group| group.count).sum::<u32>();
+        if team_size > self.num_agents {
+            return Err(format!(
+                "The team groups have {} agents, more than the {} agents of the simulation",
+                team_size, self.num_agents
+            ));
+        }
+        let followers = self
+            .team
+            .iter()
+            .filter(|group| group.role == Role::Follower)
+            .map(|group| group.count)
+            .sum::<u32>();
+        // followers only move once a scout shares a goal with them
+        if followers > 0 && !self.share_goal {
+            return Err(String::from("Followers need goal sharing"));
+        }
+        if followers > 0 && followers == self.num_agents {
+            return Err(String::from(
+                "Followers need at least one scout to find the goals",
+            ));
+        }
+        // every agent spawns on a floor tile of its own, unless they all share the start tiles
+        let floor_tiles = match maze_file {
+            Some(maze) => {
+                let on_starts =
+                    self.spawn_placement == SpawnPlacement::Start && !maze.starts.is_empty();
+                (!on_starts).then(|| maze.floor_positions().len())
+            }
+            None if self.spawn_placement == SpawnPlacement::Start => None,
+            // every cell and every passage of a perfect maze, before removing walls
+            None => Some(2 * self.grid_width * self.grid_height - 1),
+        };
+        if let Some(floor_tiles) = floor_tiles
+            && self.num_agents as usize > floor_tiles
+        {
+            return Err(format!(
+                "{} agents do not fit on the {} floor tiles of the maze",
+                self.num_agents, floor_tiles
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Width and height of a maze, written as `8` for a square maze or `16x8`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(try_from = "MapSizeValue", into = "String")]
+pub struct MapSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MapSizeValue {
+    Square(usize),
+    Rectangle(String),
+}
+
+impl MapSize {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let parse_dimension = |s: &str| s.trim().parse().map_err(|e| format!("{}", e));
+        match s.split_once('x') {
+            Some((width, height)) => {
+                Ok(Self::new(parse_dimension(width)?, parse_dimension(height)?))
+            }
+            None => {
+                let size = parse_dimension(s)?;
+                Ok(Self::new(size, size))
+            }
+        }
+    }
+}
+
+impl TryFrom<MapSizeValue> for MapSize {
+    type Error = String;
+
+    fn try_from(value: MapSizeValue) -> Result<Self, Self::Error> {
+        match value {
+            MapSizeValue::Square(size) => Ok(Self::new(size, size)),
+            MapSizeValue::Rectangle(s) => Self::parse(&s),
+        }
+    }
+}
+
+impl From<MapSize> for String {
+    fn from(size: MapSize) -> Self {
+        size.to_string()
+    }
+}
+
+impl fmt::Display for MapSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.width == self.height {
+            write!(f, "{}", self.width)
+        } else {
+            write!(f, "{}x{}", self.width, self.height)
+        }
+    }
+}
+
+/// When the agents are done with a maze that has one or more goals.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mission {
+    /// Every agent leaves the maze through any of the goals
+    #[default]
+    AnyExit,
+    /// The team visits every goal at least once, with any agent
+    CollectAll,
+}
+
+/// A setting with a fixed list of values, which the menu cycles through.
+pub trait Selectable: Copy + PartialEq + 'static {
+    const ALL: &'static [Self];
+
+    fn as_str(&self) -> &'static str;
+
+    fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn prev(&self) -> Self {
+        let index = Self::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Where the agents are placed when the simulation starts.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SpawnPlacement {
+    /// Random floor tiles, the start markers of a maze file first
+    #[default]
+    Random,
+    /// The floor tiles with the shortest path to a random one
+    Cluster,
+    /// The start markers of a maze file or the knossos start of a generated maze
+    Start,
+    /// Floor tiles as far apart from each other as possible
+    Spread,
+    /// Random floor tiles with a path of at least the spawn goal distance to every goal
+    GoalDistance,
+}
+
+impl Selectable for SpawnPlacement {
+    const ALL: &'static [Self] = &[
+        SpawnPlacement::Random,
+        SpawnPlacement::Cluster,
+        SpawnPlacement::Start,
+        SpawnPlacement::Spread,
+        SpawnPlacement::GoalDistance,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SpawnPlacement::Random => "Random",
+            SpawnPlacement::Cluster => "Cluster",
+            SpawnPlacement::Start => "Start",
+            SpawnPlacement::Spread => "Spread",
+            SpawnPlacement::GoalDistance => "Away from the goal",
+        }
+    }
+}
+
+/// How an agent picks the next tile to explore once it has no path to follow.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// Known goals first, then unexplored tiles close to the agent, the border and the other agents
+    #[default]
+    Heuristic,
+    /// Depth first search, the tile seen last
+    Dfs,
+    /// Breadth first search, the tile seen first
+    Bfs,
+    /// The tile closest to the agent
+    NearestFrontier,
+    /// A random unexplored neighbor, or a random tile in a dead end
+    RandomWalk,
+    /// Wall follower keeping the left hand on the wall
+    LeftHand,
+    /// Wall follower keeping the right hand on the wall
+    RightHand,
+}
+
+impl Selectable for Strategy {
+    const ALL: &'static [Self] = &[
+        Strategy::Heuristic,
+        Strategy::Dfs,
+        Strategy::Bfs,
+        Strategy::NearestFrontier,
+        Strategy::RandomWalk,
+        Strategy::LeftHand,
+        Strategy::RightHand,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::Heuristic => "Heuristic",
+            Strategy::Dfs => "Depth first",
+            Strategy::Bfs => "Breadth first",
+            Strategy::NearestFrontier => "Nearest frontier",
+            Strategy::RandomWalk => "Random walk",
+            Strategy::LeftHand => "Left hand",
+            Strategy::RightHand => "Right hand",
+        }
+    }
+}
+
+/// What an agent sees around it, by default only the four tiles next to it.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sensor {
+    /// How many tiles away the agent sees
+    pub radius: usize,
+    /// Only along straight lines until a wall, instead of every tile in the radius
+    pub line_of_sight: bool,
+    /// Also diagonally, a square instead of a diamond around the agent
+    pub diagonal: bool,
+}
+
+impl Default for Sensor {
+    fn default() -> Self {
+        Self {
+            radius: 1,
+            line_of_sight: false,
+            diagonal: false,
+        }
+    }
+}
+
+/// What an agent does in a mixed team.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    /// Explores the maze with its strategy
+    #[default]
+    Scout,
+    /// Waits where it spawned until it knows a goal to head to
+    Follower,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Scout => "Scout",
+            Role::Follower => "Follower",
         }
     }
 }
 
+/// A group of agents of a mixed team, written as `3:scout:nearest-frontier`
+/// or `2:follower` to use the strategy of the simulation.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TeamGroup {
+    pub count: u32,
+    pub role: Role,
+    pub strategy: Option<Strategy>,
+}
+
+impl TeamGroup {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let parts = s.split(':').map(|part| part.trim()).collect::<Vec<&str>>();
+        let (count, role, strategy) = match parts[..] {
+            [count, role] => (count, role, None),
+            [count, role, strategy] => (count, role, Some(strategy)),
+            _ => {
+                return Err(format!(
+                    "Team group '{}' needs to be COUNT:ROLE or COUNT:ROLE:STRATEGY",
+                    s
+                ));
+            }
+        };
+        Ok(Self {
+            count: count
+                .parse()
+                .map_err(|e| format!("Invalid agent count '{}': {}", count, e))?,
+            role: Role::from_str(role, true)?,
+            strategy: strategy
+                .map(|strategy| Strategy::from_str(strategy, true))
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<String> for TeamGroup {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+impl From<TeamGroup> for String {
+    fn from(group: TeamGroup) -> Self {
+        group.to_string()
+    }
+}
+
+impl fmt::Display for TeamGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |value: Option<clap::builder::PossibleValue>| {
+            value.map_or(String::new(), |value| value.get_name().to_string())
+        };
+        write!(f, "{}:{}", self.count, name(self.role.to_possible_value()))?;
+        if let Some(strategy) = self.strategy {
+            write!(f, ":{}", name(strategy.to_possible_value()))?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum SeedType {
     Random,
@@ -55,8 +440,33 @@ pub enum SimulationSpeed {
     X128,
 }
 
-impl SimulationSpeed {
-    pub fn next(&self) -> Self {
+impl Selectable for SimulationSpeed {
+    const ALL: &'static [Self] = &[
+        SimulationSpeed::X1,
+        SimulationSpeed::X2,
+        SimulationSpeed::X4,
+        SimulationSpeed::X8,
+        SimulationSpeed::X16,
+        SimulationSpeed::X32,
+        SimulationSpeed::X64,
+        SimulationSpeed::X128,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SimulationSpeed::X1 => "1x",
+            SimulationSpeed::X2 => "2x",
+            SimulationSpeed::X4 => "4x",
+            SimulationSpeed::X8 => "8x",
+            SimulationSpeed::X16 => "16x",
+            SimulationSpeed::X32 => "32x",
+            SimulationSpeed::X64 => "64x",
+            SimulationSpeed::X128 => "128x",
+        }
+    }
+
+    // the speed stops at the slowest and the fastest one instead of wrapping around
+    fn next(&self) -> Self {
         match self {
             SimulationSpeed::X1 => SimulationSpeed::X2,
             SimulationSpeed::X2 => SimulationSpeed::X4,
@@ -69,7 +479,7 @@ impl SimulationSpeed {
         }
     }
 
-    pub fn prev(&self) -> Self {
+    fn prev(&self) -> Self {
         match self {
             SimulationSpeed::X1 => SimulationSpeed::X1,
             SimulationSpeed::X2 => SimulationSpeed::X1,
@@ -81,7 +491,9 @@ impl SimulationSpeed {
             SimulationSpeed::X128 => SimulationSpeed::X64,
         }
     }
+}
 
+impl SimulationSpeed {
     pub fn to_duration(&self) -> Duration {
         let secs = 1.0
             / match self {
@@ -96,17 +508,178 @@ impl SimulationSpeed {
             };
         Duration::from_secs_f32(secs)
     }
+}
 
-    pub fn as_str(&self) -> &'static str {
+#[derive(
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    clap::ValueEnum,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum MazeAlgorithm {
+    #[default]
+    GrowingTreeRandom,
+    GrowingTreeNewest,
+    GrowingTreeOldest,
+    GrowingTreeMiddle,
+    RecursiveBacktracking,
+    Kruskal,
+    Prim,
+    /// Not reproducible, knossos iterates over a `HashMap` to build it
+    Eller,
+    BinaryTree,
+    Sidewinder,
+    AldousBroder,
+    HuntAndKill,
+}
+
+impl Selectable for MazeAlgorithm {
+    const ALL: &'static [Self] = &[
+        MazeAlgorithm::GrowingTreeRandom,
+        MazeAlgorithm::GrowingTreeNewest,
+        MazeAlgorithm::GrowingTreeOldest,
+        MazeAlgorithm::GrowingTreeMiddle,
+        MazeAlgorithm::RecursiveBacktracking,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Eller,
+        MazeAlgorithm::BinaryTree,
+        MazeAlgorithm::Sidewinder,
+        MazeAlgorithm::AldousBroder,
+        MazeAlgorithm::HuntAndKill,
+    ];
+
+    fn as_str(&self) -> &'static str {
         match self {
-            SimulationSpeed::X1 => "1x",
-            SimulationSpeed::X2 => "2x",
-            SimulationSpeed::X4 => "4x",
-            SimulationSpeed::X8 => "8x",
-            SimulationSpeed::X16 => "16x",
-            SimulationSpeed::X32 => "32x",
-            SimulationSpeed::X64 => "64x",
-            SimulationSpeed::X128 => "128x",
+            MazeAlgorithm::GrowingTreeRandom => "Growing tree (random)",
+            MazeAlgorithm::GrowingTreeNewest => "Growing tree (newest)",
+            MazeAlgorithm::GrowingTreeOldest => "Growing tree (oldest)",
+            MazeAlgorithm::GrowingTreeMiddle => "Growing tree (middle)",
+            MazeAlgorithm::RecursiveBacktracking => "Recursive backtracking",
+            MazeAlgorithm::Kruskal => "Kruskal",
+            MazeAlgorithm::Prim => "Prim",
+            MazeAlgorithm::Eller => "Eller",
+            MazeAlgorithm::BinaryTree => "Binary tree",
+            MazeAlgorithm::Sidewinder => "Sidewinder",
+            MazeAlgorithm::AldousBroder => "Aldous-Broder",
+            MazeAlgorithm::HuntAndKill => "Hunt-and-kill",
         }
     }
 }
+
+impl MazeAlgorithm {
+    /// Whether the same seed always generates the same maze.
+    pub fn is_reproducible(&self) -> bool {
+        *self != MazeAlgorithm::Eller
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_map_size() {
+        assert_eq!(MapSize::parse("8"), Ok(MapSize::new(8, 8)));
+        assert_eq!(MapSize::parse("32x8"), Ok(MapSize::new(32, 8)));
+        assert_eq!(MapSize::parse(" 16 x 12 "), Ok(MapSize::new(16, 12)));
+        assert!(MapSize::parse("").is_err());
+        assert!(MapSize::parse("8x").is_err());
+        assert!(MapSize::parse("x8").is_err());
+        assert!(MapSize::parse("8x8x8").is_err());
+        assert!(MapSize::parse("-8").is_err());
+    }
+
+    #[test]
+    fn map_size_round_trip() {
+        for size in [MapSize::new(8, 8), MapSize::new(32, 8)] {
+            assert_eq!(MapSize::parse(&size.to_string()), Ok(size));
+        }
+        assert_eq!(MapSize::new(8, 8).to_string(), "8");
+        assert_eq!(MapSize::new(32, 8).to_string(), "32x8");
+    }
+
+    #[test]
+    fn parse_team_group() {
+        assert_eq!(
+            TeamGroup::parse("3:scout:nearest-frontier"),
+            Ok(TeamGroup {
+                count: 3,
+                role: Role::Scout,
+                strategy: Some(Strategy::NearestFrontier),
+            })
+        );
+        assert_eq!(
+            TeamGroup::parse(" 2 : Follower "),
+            Ok(TeamGroup {
+                count: 2,
+                role: Role::Follower,
+                strategy: None,
+            })
+        );
+        assert!(TeamGroup::parse("2").is_err());
+        assert!(TeamGroup::parse("two:scout").is_err());
+        assert!(TeamGroup::parse("2:leader").is_err());
+        assert!(TeamGroup::parse("2:scout:teleport").is_err());
+        assert!(TeamGroup::parse("2:scout:dfs:bfs").is_err());
+    }
+
+    #[test]
+    fn team_group_round_trip() {
+        for group in [
+            "3:scout:nearest-frontier",
+            "2:follower",
+            "1:scout:left-hand",
+        ] {
+            assert_eq!(TeamGroup::parse(group).unwrap().to_string(), group);
+        }
+    }
+
+    #[test]
+    fn validate_team() {
+        let settings = |num_agents, team: &str, share_goal| SimulationSettings {
+            num_agents,
+            team: team
+                .split(',')
+                .map(|g| TeamGroup::parse(g).unwrap())
+                .collect(),
+            share_goal,
+            ..Default::default()
+        };
+        assert!(
+            settings(5, "3:scout:dfs,2:follower", true)
+                .validate(None)
+                .is_ok()
+        );
+        assert!(settings(5, "2:follower", true).validate(None).is_ok());
+        assert!(
+            settings(4, "3:scout,2:follower", true)
+                .validate(None)
+                .is_err()
+        );
+        assert!(settings(5, "2:follower", false).validate(None).is_err());
+        assert!(settings(2, "2:follower", true).validate(None).is_err());
+        assert!(settings(2, "2:scout:bfs", false).validate(None).is_ok());
+    }
+
+    #[test]
+    fn deserialize_map_size() {
+        let sizes: Vec<MapSize> = serde_json::from_str(r#"[8, "16x8", "12"]"#).unwrap();
+        assert_eq!(
+            sizes,
+            [
+                MapSize::new(8, 8),
+                MapSize::new(16, 8),
+                MapSize::new(12, 12)
+            ]
+        );
+    }
+}
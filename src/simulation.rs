@@ -5,6 +5,9 @@ use crate::{GameState, settings::SimulationSettings};
 #[derive(Event)]
 pub struct SimulationStepEvent;
 
+#[derive(Event)]
+pub struct SimulationTimeoutEvent;
+
 #[derive(Resource, Default)]
 pub struct SimulationStepData {
     pub timesteps: u64,
@@ -30,11 +33,16 @@ impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(SimulationStepData::default());
         app.add_event::<SimulationStepEvent>();
+        app.add_event::<SimulationTimeoutEvent>();
         app.add_systems(OnEnter(GameState::Simulation), start_simulation);
         app.add_systems(
             FixedUpdate,
             tick_simulation.run_if(in_state(GameState::Simulation)),
         );
+        app.add_systems(
+            FixedPostUpdate,
+            check_timestep_limit.run_if(on_event::<SimulationStepEvent>),
+        );
     }
 }
 
@@ -43,8 +51,7 @@ fn start_simulation(
     settings: Res<SimulationSettings>,
     mut time: ResMut<Time<Fixed>>,
 ) {
-    let seconds = settings.simulation_speed.to_duration().as_secs_f64();
-    time.set_timestep_seconds(seconds);
+    time.set_timestep(settings.simulation_speed.to_duration());
     step.reset();
 }
 
@@ -57,3 +64,17 @@ pub fn tick_simulation(
         event.write(SimulationStepEvent);
     }
 }
+
+pub fn check_timestep_limit(
+    mut step: ResMut<SimulationStepData>,
+    settings: Res<SimulationSettings>,
+    mut event: EventWriter<SimulationTimeoutEvent>,
+) {
+    let limit_reached = settings
+        .max_timesteps
+        .is_some_and(|max_timesteps| step.timesteps >= max_timesteps);
+    if !step.stopped && limit_reached {
+        step.stop();
+        event.write(SimulationTimeoutEvent);
+    }
+}
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    experiment::ExperimentConfig,
+    headless::SimulationSummary,
+    settings::{MapSize, MazeAlgorithm, Selectable},
+};
+
+/// Two-sided 95% critical values of the t-distribution for 1 to 30 degrees of freedom.
+const T_CRITICAL_VALUES: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+const Z_CRITICAL_VALUE: f64 = 1.960;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Statistics {
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub ci95: (f64, f64),
+}
+
+impl Statistics {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let n = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        // sample standard deviation, a single run has no spread to speak of
+        let std_dev = if n > 1 {
+            let variance = sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+        let critical_value = T_CRITICAL_VALUES
+            .get(n.saturating_sub(2))
+            .copied()
+            .unwrap_or(Z_CRITICAL_VALUE);
+        let margin = critical_value * std_dev / (n as f64).sqrt();
+        Some(Self {
+            mean,
+            median,
+            std_dev,
+            min: sorted[0],
+            max: sorted[n - 1],
+            ci95: (mean - margin, mean + margin),
+        })
+    }
+}
+
+/// Aggregates of every seed run with the same agent count, map size, maze algorithm and
+/// communication preset. The timesteps only cover the completed runs, as the others
+/// were stopped by the timestep limit.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConfigurationStatistics {
+    pub num_agents: u32,
+    pub map_size: MapSize,
+    pub maze_algorithm: MazeAlgorithm,
+    pub communication: String,
+    pub runs: usize,
+    pub completed_runs: usize,
+    pub completion_rate: f64,
+    pub timesteps: Option<Statistics>,
+    pub explored_tiles: Statistics,
+}
+
+/// The runs are in the order of `ExperimentConfig::create_settings`, every seed of
+/// a configuration one after the other.
+pub fn create_statistics(
+    experiment: &ExperimentConfig,
+    runs: &[SimulationSummary],
+) -> Vec<ConfigurationStatistics> {
+    let mut statistics = Vec::new();
+    let mut groups = runs.chunks(experiment.seeds.len().max(1));
+    for num_agents in &experiment.num_agents {
+        for map_size in &experiment.map_size {
+            for maze_algorithm in &experiment.maze_algorithm {
+                for (name, _) in &experiment.communication {
+                    let Some(group) = groups.next() else {
+                        return statistics;
+                    };
+                    let completed = group
+                        .iter()
+                        .filter(|run| run.results.completed)
+                        .collect::<Vec<&SimulationSummary>>();
+                    let timesteps = completed
+                        .iter()
+                        .map(|run| run.results.timesteps as f64)
+                        .collect::<Vec<f64>>();
+                    let explored_tiles = group
+                        .iter()
+                        .map(|run| run.results.total_unique_explored_titles as f64)
+                        .collect::<Vec<f64>>();
+                    let Some(explored_tiles) = Statistics::from_samples(&explored_tiles) else {
+                        continue;
+                    };
+                    statistics.push(ConfigurationStatistics {
+                        num_agents: *num_agents,
+                        map_size: *map_size,
+                        maze_algorithm: *maze_algorithm,
+                        communication: name.clone(),
+                        runs: group.len(),
+                        completed_runs: completed.len(),
+                        completion_rate: completed.len() as f64 / group.len() as f64,
+                        timesteps: Statistics::from_samples(&timesteps),
+                        explored_tiles,
+                    });
+                }
+            }
+        }
+    }
+    statistics
+}
+
+pub fn print_statistics_table(statistics: &[ConfigurationStatistics]) {
+    print_metric_table("Timesteps (completed runs)", statistics, |s| {
+        s.timesteps.as_ref()
+    });
+    println!();
+    print_metric_table("Explored tiles", statistics, |s| Some(&s.explored_tiles));
+}
+
+fn print_metric_table(
+    title: &str,
+    statistics: &[ConfigurationStatistics],
+    metric: impl Fn(&ConfigurationStatistics) -> Option<&Statistics>,
+) {
+    let algorithm_width = statistics
+        .iter()
+        .map(|s| s.maze_algorithm.as_str().len())
+        .max()
+        .unwrap_or(0)
+        .max("algorithm".len());
+    let name_width = statistics
+        .iter()
+        .map(|s| s.communication.len())
+        .max()
+        .unwrap_or(0)
+        .max("communication".len());
+    println!("{}", title);
+    println!(
+        "{:>6} {:>5} {:<algorithm_width$} {:<name_width$} {:>7} | {:>8} {:>8} {:>8} {:>6} {:>6} {:>19}",
+        "agents",
+        "map",
+        "algorithm",
+        "communication",
+        "runs",
+        "mean",
+        "median",
+        "std",
+        "min",
+        "max",
+        "95% ci",
+    );
+    for s in statistics {
+        let Some(m) = metric(s) else {
+            println!(
+                "{:>6} {:>5} {:<algorithm_width$} {:<name_width$} {:>7} | {:>8}",
+                s.num_agents,
+                s.map_size.to_string(),
+                s.maze_algorithm.as_str(),
+                s.communication,
+                format!("{}/{}", s.completed_runs, s.runs),
+                "-",
+            );
+            continue;
+        };
+        println!(
+            "{:>6} {:>5} {:<algorithm_width$} {:<name_width$} {:>7} | {:>8.1} {:>8.1} {:>8.1} {:>6} {:>6} {:>19}",
+            s.num_agents,
+            s.map_size.to_string(),
+            s.maze_algorithm.as_str(),
+            s.communication,
+            format!("{}/{}", s.completed_runs, s.runs),
+            m.mean,
+            m.median,
+            m.std_dev,
+            m.min,
+            m.max,
+            format!("[{:.1}, {:.1}]", m.ci95.0, m.ci95.1),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_samples() {
+        assert!(Statistics::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn single_sample() {
+        let statistics = Statistics::from_samples(&[4.0]).unwrap();
+        assert_eq!(statistics.mean, 4.0);
+        assert_eq!(statistics.median, 4.0);
+        assert_eq!(statistics.std_dev, 0.0);
+        assert_eq!(statistics.ci95, (4.0, 4.0));
+    }
+
+    #[test]
+    fn even_sample_count() {
+        let statistics = Statistics::from_samples(&[8.0, 2.0, 4.0, 6.0]).unwrap();
+        assert_eq!(statistics.mean, 5.0);
+        assert_eq!(statistics.median, 5.0);
+        assert_eq!((statistics.min, statistics.max), (2.0, 8.0));
+        // variance of 20 / 3 with three degrees of freedom
+        let std_dev = (20.0f64 / 3.0).sqrt();
+        assert!((statistics.std_dev - std_dev).abs() < 1e-9);
+        let margin = 3.182 * std_dev / 2.0;
+        assert!((statistics.ci95.0 - (5.0 - margin)).abs() < 1e-9);
+        assert!((statistics.ci95.1 - (5.0 + margin)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn odd_sample_count() {
+        let statistics = Statistics::from_samples(&[3.0, 1.0, 100.0]).unwrap();
+        assert_eq!(statistics.median, 3.0);
+        assert_eq!(statistics.mean, 104.0 / 3.0);
+    }
+
+    #[test]
+    fn many_samples_use_normal_critical_value() {
+        let samples = (0..40).map(|i| (i % 2) as f64).collect::<Vec<f64>>();
+        let statistics = Statistics::from_samples(&samples).unwrap();
+        let margin = Z_CRITICAL_VALUE * statistics.std_dev / 40.0f64.sqrt();
+        assert!((statistics.ci95.1 - statistics.mean - margin).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,26 @@
+use rand::rngs::StdRng;
+
+use crate::utils::heuristics;
+
+use super::{ExplorationStrategy, FrontierContext};
+
+/// Known goals first, then the tiles nobody explored, close to the agent, close
+/// to the border and close to the other agents.
+pub struct Heuristic;
+
+impl ExplorationStrategy for Heuristic {
+    fn order_frontier(
+        &self,
+        frontier: &mut [(usize, usize)],
+        context: &FrontierContext,
+        _rng: &mut StdRng,
+    ) {
+        frontier.sort_by(|n1, n2| {
+            heuristics::goals_cmp(*n1, *n2, context.goals)
+                .then_with(|| heuristics::explored_cmp(*n1, *n2, context.explored))
+                .then_with(|| heuristics::manhattan_cmp(*n1, *n2, context.position))
+                .then_with(|| heuristics::border_cmp(*n1, *n2, context.width, context.height))
+                .then_with(|| heuristics::neighbors_cmp(*n1, *n2, context.agent_positions))
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::{settings::Strategy, utils::grid::TileSet};
+
+mod heuristic;
+mod random_walk;
+mod search;
+mod wall_follower;
+
+/// What an agent knows when it picks the next frontier tile to explore.
+pub struct FrontierContext<'a> {
+    pub position: (usize, usize),
+    pub previous_position: (usize, usize),
+    /// The goals the agent knows about and still needs to visit
+    pub goals: &'a [(usize, usize)],
+    /// The tiles explored by any agent, when sharing the maze
+    pub explored: &'a TileSet,
+    /// The positions of the agents, when sharing them
+    pub agent_positions: &'a Vec<(usize, usize)>,
+    /// How far the agent sees, the frontier tiles in view are at most this far
+    pub sensor_radius: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Picks where an agent explores next once it has no path to follow. New
+/// strategies only need an implementation and a `Strategy` to create it from.
+pub trait ExplorationStrategy: Send + Sync {
+    /// Reorders the frontier of the agent, the last tile is the one explored next.
+    fn order_frontier(
+        &self,
+        frontier: &mut [(usize, usize)],
+        context: &FrontierContext,
+        rng: &mut StdRng,
+    );
+}
+
+#[derive(Component)]
+pub struct AgentStrategy(pub Box<dyn ExplorationStrategy>);
+
+pub fn create_strategy(strategy: Strategy) -> Box<dyn ExplorationStrategy> {
+    match strategy {
+        Strategy::Heuristic => Box::new(heuristic::Heuristic),
+        Strategy::Dfs => Box::new(search::DepthFirst),
+        Strategy::Bfs => Box::new(search::BreadthFirst),
+        Strategy::NearestFrontier => Box::new(search::NearestFrontier),
+        Strategy::RandomWalk => Box::new(random_walk::RandomWalk),
+        Strategy::LeftHand => Box::new(wall_follower::WallFollower::left()),
+        Strategy::RightHand => Box::new(wall_follower::WallFollower::right()),
+    }
+}
+
+/// Moves the tile to the end of the frontier if it is in there, keeping the
+/// order of the others.
+fn explore_next(frontier: &mut [(usize, usize)], tile: (usize, usize)) {
+    if let Some(index) = frontier.iter().position(|n| *n == tile) {
+        frontier[index..].rotate_left(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn order(
+        strategy: Strategy,
+        frontier: &[(usize, usize)],
+        context: &FrontierContext,
+    ) -> Vec<(usize, usize)> {
+        let mut frontier = frontier.to_vec();
+        let mut rng = StdRng::seed_from_u64(0);
+        create_strategy(strategy).order_frontier(&mut frontier, context, &mut rng);
+        frontier
+    }
+
+    fn create_context<'a>(
+        position: (usize, usize),
+        previous_position: (usize, usize),
+        explored: &'a TileSet,
+        agent_positions: &'a Vec<(usize, usize)>,
+    ) -> FrontierContext<'a> {
+        FrontierContext {
+            position,
+            previous_position,
+            goals: &[],
+            explored,
+            agent_positions,
+            sensor_radius: 1,
+            width: 11,
+            height: 11,
+        }
+    }
+
+    const FRONTIER: [(usize, usize); 4] = [(1, 1), (5, 4), (9, 9), (6, 5)];
+
+    #[test]
+    fn depth_first_keeps_the_last_tile_seen() {
+        let (explored, agents) = (TileSet::new(11, 11), Vec::new());
+        let context = create_context((5, 5), (5, 5), &explored, &agents);
+        assert_eq!(order(Strategy::Dfs, &FRONTIER, &context), FRONTIER);
+    }
+
+    #[test]
+    fn breadth_first_takes_the_first_tile_seen() {
+        let (explored, agents) = (TileSet::new(11, 11), Vec::new());
+        let context = create_context((5, 5), (5, 5), &explored, &agents);
+        assert_eq!(
+            order(Strategy::Bfs, &FRONTIER, &context),
+            [(5, 4), (9, 9), (6, 5), (1, 1)]
+        );
+        assert!(order(Strategy::Bfs, &[], &context).is_empty());
+    }
+
+    #[test]
+    fn nearest_frontier_takes_the_last_seen_of_the_closest() {
+        let (explored, agents) = (TileSet::new(11, 11), Vec::new());
+        let context = create_context((5, 5), (5, 5), &explored, &agents);
+        assert_eq!(
+            order(Strategy::NearestFrontier, &FRONTIER, &context),
+            [(1, 1), (9, 9), (5, 4), (6, 5)]
+        );
+    }
+
+    #[test]
+    fn heuristic_prefers_goals_then_unexplored_tiles() {
+        let (mut explored, agents) = (TileSet::new(11, 11), Vec::new());
+        explored.insert((5, 4));
+        let mut context = create_context((5, 5), (5, 5), &explored, &agents);
+        let next = |context: &FrontierContext| {
+            *order(Strategy::Heuristic, &FRONTIER, context)
+                .last()
+                .unwrap()
+        };
+        assert_eq!(next(&context), (6, 5));
+        context.goals = &[(9, 9)];
+        assert_eq!(next(&context), (9, 9));
+    }
+
+    #[test]
+    fn heuristic_breaks_ties_by_border_and_agents() {
+        let (explored, mut agents) = (TileSet::new(11, 11), Vec::new());
+        // as far from the agent, the one closer to the border goes last
+        let frontier = [(2, 1), (3, 2)];
+        let context = create_context((2, 2), (2, 2), &explored, &agents);
+        assert_eq!(
+            *order(Strategy::Heuristic, &frontier, &context)
+                .last()
+                .unwrap(),
+            (2, 1)
+        );
+        // as far from the agent and the border, the one next to another agent goes last
+        let frontier = [(5, 4), (4, 5)];
+        agents.push((3, 5));
+        let context = create_context((5, 5), (5, 5), &explored, &agents);
+        assert_eq!(
+            *order(Strategy::Heuristic, &frontier, &context)
+                .last()
+                .unwrap(),
+            (4, 5)
+        );
+        let agents = vec![(5, 3)];
+        let context = create_context((5, 5), (5, 5), &explored, &agents);
+        assert_eq!(
+            *order(Strategy::Heuristic, &frontier, &context)
+                .last()
+                .unwrap(),
+            (5, 4)
+        );
+    }
+
+    #[test]
+    fn random_walk_stays_in_view() {
+        let (explored, agents) = (TileSet::new(11, 11), Vec::new());
+        let context = create_context((5, 5), (5, 5), &explored, &agents);
+        for seed in 0..16 {
+            let mut frontier = FRONTIER.to_vec();
+            let mut rng = StdRng::seed_from_u64(seed);
+            create_strategy(Strategy::RandomWalk).order_frontier(&mut frontier, &context, &mut rng);
+            assert!([(5, 4), (6, 5)].contains(frontier.last().unwrap()));
+            let mut sorted = frontier.clone();
+            sorted.sort();
+            assert_eq!(sorted, [(1, 1), (5, 4), (6, 5), (9, 9)]);
+        }
+    }
+
+    #[test]
+    fn wall_followers_turn_to_their_side_first() {
+        let (explored, agents) = (TileSet::new(11, 11), Vec::new());
+        // heading up from (5, 6)
+        let context = create_context((5, 5), (5, 6), &explored, &agents);
+        let neighbors = [(6, 5), (5, 4), (4, 5), (5, 6)];
+        let next = |strategy, frontier: &[(usize, usize)]| {
+            *order(strategy, frontier, &context).last().unwrap()
+        };
+        assert_eq!(next(Strategy::LeftHand, &neighbors), (4, 5));
+        assert_eq!(next(Strategy::RightHand, &neighbors), (6, 5));
+        // straight on when the wall side is closed
+        assert_eq!(next(Strategy::LeftHand, &neighbors[..2]), (5, 4));
+        assert_eq!(next(Strategy::RightHand, &neighbors[1..]), (5, 4));
+        // back the way it came only when nothing else is left
+        assert_eq!(next(Strategy::LeftHand, &[(5, 6), (1, 1)]), (5, 6));
+    }
+}
@@ -0,0 +1,37 @@
+use rand::{Rng, rngs::StdRng};
+
+use crate::utils::heuristics;
+
+use super::{ExplorationStrategy, FrontierContext};
+
+/// A random frontier tile in view, next to the agent with the default sensor,
+/// or any random frontier tile when the agent is in a dead end.
+pub struct RandomWalk;
+
+impl ExplorationStrategy for RandomWalk {
+    fn order_frontier(
+        &self,
+        frontier: &mut [(usize, usize)],
+        context: &FrontierContext,
+        rng: &mut StdRng,
+    ) {
+        if frontier.is_empty() {
+            return;
+        }
+        let in_view = frontier
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| {
+                (1..=context.sensor_radius as i32)
+                    .contains(&heuristics::manhattan(**n, context.position))
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
+        let index = if in_view.is_empty() {
+            rng.random_range(0..frontier.len())
+        } else {
+            in_view[rng.random_range(0..in_view.len())]
+        };
+        frontier[index..].rotate_left(1);
+    }
+}
@@ -0,0 +1,46 @@
+use rand::rngs::StdRng;
+
+use crate::utils::heuristics;
+
+use super::{ExplorationStrategy, FrontierContext};
+
+/// The tile seen last, new tiles are added to the end of the frontier.
+pub struct DepthFirst;
+
+impl ExplorationStrategy for DepthFirst {
+    fn order_frontier(
+        &self,
+        _frontier: &mut [(usize, usize)],
+        _context: &FrontierContext,
+        _rng: &mut StdRng,
+    ) {
+    }
+}
+
+/// The tile seen first.
+pub struct BreadthFirst;
+
+impl ExplorationStrategy for BreadthFirst {
+    fn order_frontier(
+        &self,
+        frontier: &mut [(usize, usize)],
+        _context: &FrontierContext,
+        _rng: &mut StdRng,
+    ) {
+        frontier.rotate_left(1.min(frontier.len()));
+    }
+}
+
+/// The tile closest to the agent, the one seen last between equally close ones.
+pub struct NearestFrontier;
+
+impl ExplorationStrategy for NearestFrontier {
+    fn order_frontier(
+        &self,
+        frontier: &mut [(usize, usize)],
+        context: &FrontierContext,
+        _rng: &mut StdRng,
+    ) {
+        frontier.sort_by(|n1, n2| heuristics::manhattan_cmp(*n1, *n2, context.position));
+    }
+}
@@ -0,0 +1,51 @@
+use rand::rngs::StdRng;
+
+use super::{ExplorationStrategy, FrontierContext, explore_next};
+
+/// Keeps one hand on the wall, turning to that side whenever it can. Once
+/// every neighbor is explored it backtracks like a depth first search.
+pub struct WallFollower {
+    /// Turns right instead of left first
+    right_hand: bool,
+}
+
+impl WallFollower {
+    pub fn left() -> Self {
+        Self { right_hand: false }
+    }
+
+    pub fn right() -> Self {
+        Self { right_hand: true }
+    }
+}
+
+impl ExplorationStrategy for WallFollower {
+    fn order_frontier(
+        &self,
+        frontier: &mut [(usize, usize)],
+        context: &FrontierContext,
+        _rng: &mut StdRng,
+    ) {
+        let (x, y) = context.position;
+        let (px, py) = context.previous_position;
+        // facing up before the first move
+        let (dx, dy) = match (x as isize - px as isize, y as isize - py as isize) {
+            (0, 0) => (0, -1),
+            heading => heading,
+        };
+        let left = (dy, -dx);
+        let right = (-dy, dx);
+        let turns = if self.right_hand {
+            [right, (dx, dy), left, (-dx, -dy)]
+        } else {
+            [left, (dx, dy), right, (-dx, -dy)]
+        };
+        // the preferred turns go last, so the best one ends up at the end
+        for (tx, ty) in turns.iter().rev() {
+            explore_next(
+                frontier,
+                (x.wrapping_add_signed(*tx), y.wrapping_add_signed(*ty)),
+            );
+        }
+    }
+}
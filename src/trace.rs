@@ -0,0 +1,168 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    agent::{self, Agent, SharedMazeKnowledge},
+    simulation::{SimulationStepData, SimulationStepEvent},
+};
+
+pub struct TracePlugin;
+
+impl Plugin for TracePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            write_trace
+                .after(agent::move_agent_path)
+                .run_if(resource_exists::<TraceWriter>.and(on_event::<SimulationStepEvent>)),
+        );
+    }
+}
+
+enum TraceFormat {
+    Csv,
+    JsonLines,
+}
+
+/// Writes one record per timestep, as `.csv` (one row per agent) or `.jsonl`.
+#[derive(Resource)]
+pub struct TraceWriter {
+    writer: BufWriter<File>,
+    format: TraceFormat,
+    /// Bytes written to the file so far
+    position: u64,
+    /// The timestep of every record and where it starts in the file
+    records: Vec<(u64, u64)>,
+}
+
+#[derive(Serialize)]
+struct TraceRecord {
+    timestep: u64,
+    shared_explored_tiles: usize,
+    agents: Vec<AgentTrace>,
+}
+
+#[derive(Serialize)]
+struct AgentTrace {
+    id: usize,
+    position: (usize, usize),
+    current_goal: (usize, usize),
+    path_length: usize,
+    frontier_size: usize,
+}
+
+impl TraceWriter {
+    pub fn create(path: &str) -> Result<Self, String> {
+        let format = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => TraceFormat::Csv,
+            Some("jsonl") => TraceFormat::JsonLines,
+            _ => return Err(String::from("Trace file needs to be a .csv or .jsonl file")),
+        };
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create trace file '{}': {}", path, e))?;
+        let mut trace = Self {
+            writer: BufWriter::new(file),
+            format,
+            position: 0,
+            records: Vec::new(),
+        };
+        if let TraceFormat::Csv = trace.format {
+            trace
+                .write_bytes(
+                    b"timestep,agent,x,y,goal_x,goal_y,path_length,frontier_size,shared_explored_tiles\n",
+                )
+                .map_err(|e| format!("{}", e))?;
+        }
+        Ok(trace)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &TraceRecord) -> std::io::Result<()> {
+        let mut bytes = Vec::new();
+        match self.format {
+            TraceFormat::Csv => {
+                for agent in &record.agents {
+                    writeln!(
+                        bytes,
+                        "{},{},{},{},{},{},{},{},{}",
+                        record.timestep,
+                        agent.id,
+                        agent.position.0,
+                        agent.position.1,
+                        agent.current_goal.0,
+                        agent.current_goal.1,
+                        agent.path_length,
+                        agent.frontier_size,
+                        record.shared_explored_tiles
+                    )?;
+                }
+            }
+            TraceFormat::JsonLines => {
+                serde_json::to_writer(&mut bytes, record)?;
+                writeln!(bytes)?;
+            }
+        }
+        self.records.push((record.timestep, self.position));
+        self.write_bytes(&bytes)
+    }
+
+    /// Removes the records after the timestep, which get written again once
+    /// the simulation continues from it.
+    pub fn rewind(&mut self, timestep: u64) -> std::io::Result<()> {
+        let kept = self.records.partition_point(|(t, _)| *t <= timestep);
+        let Some((_, start)) = self.records.get(kept).copied() else {
+            return Ok(());
+        };
+        self.records.truncate(kept);
+        self.writer.flush()?;
+        let file = self.writer.get_mut();
+        file.set_len(start)?;
+        file.seek(SeekFrom::Start(start))?;
+        self.position = start;
+        Ok(())
+    }
+}
+
+fn write_trace(
+    mut trace: ResMut<TraceWriter>,
+    query: Query<&Agent>,
+    knowledge: Res<SharedMazeKnowledge>,
+    step: Res<SimulationStepData>,
+) {
+    let mut agents = query
+        .iter()
+        .map(|agent| AgentTrace {
+            id: agent.id,
+            position: agent.position,
+            current_goal: agent.current_goal,
+            path_length: agent.current_path.as_ref().map_or(0, |path| path.len()),
+            frontier_size: agent.frontier.len(),
+        })
+        .collect::<Vec<AgentTrace>>();
+    agents.sort_by_key(|agent| agent.id);
+    let record = TraceRecord {
+        timestep: step.timesteps,
+        shared_explored_tiles: knowledge.explored_tiles.len(),
+        agents,
+    };
+    trace
+        .write_record(&record)
+        .expect("Failed to write to trace file!");
+    if step.stopped {
+        trace
+            .writer
+            .flush()
+            .expect("Failed to write to trace file!");
+    }
+}
@@ -4,16 +4,22 @@ use crate::{GameState, settings::SimulationSettings};
 
 use super::{MainMenuItem, spawn_menu};
 
+#[derive(Clone, Copy)]
+pub enum GridDimension {
+    Width,
+    Height,
+}
+
 #[derive(Component)]
-struct GridDecreaseButton;
+struct GridDecreaseButton(GridDimension);
 
 #[derive(Component)]
-struct GridIncreaseButton;
+struct GridIncreaseButton(GridDimension);
 
 #[derive(Component)]
-struct GridTextLabel;
+struct GridTextLabel(GridDimension);
 
-pub fn grid_selector_bundle() -> impl Bundle + use<> {
+pub fn grid_selector_bundle(dimension: GridDimension) -> impl Bundle + use<> {
     (
         MainMenuItem,
         Node {
@@ -25,7 +31,7 @@ pub fn grid_selector_bundle() -> impl Bundle + use<> {
         children![
             (
                 MainMenuItem,
-                GridDecreaseButton,
+                GridDecreaseButton(dimension),
                 Button,
                 BorderRadius::MAX,
                 BorderColor(Color::BLACK),
@@ -47,11 +53,15 @@ pub fn grid_selector_bundle() -> impl Bundle + use<> {
                     justify_content: JustifyContent::Center,
                     ..default()
                 },
-                children![(GridTextLabel, Text::default(), TextColor(Color::WHITE))]
+                children![(
+                    GridTextLabel(dimension),
+                    Text::default(),
+                    TextColor(Color::WHITE)
+                )]
             ),
             (
                 MainMenuItem,
-                GridIncreaseButton,
+                GridIncreaseButton(dimension),
                 Button,
                 BorderRadius::MAX,
                 BorderColor(Color::BLACK),
@@ -90,32 +100,44 @@ impl Plugin for GridSelectorPlugin {
 }
 
 fn grid_decrease(
-    query: Query<&Interaction, (Changed<Interaction>, With<GridDecreaseButton>)>,
+    query: Query<(&Interaction, &GridDecreaseButton), Changed<Interaction>>,
     mut settings: ResMut<SimulationSettings>,
 ) {
-    for interation in query {
+    for (interation, button) in query {
         if let Interaction::Pressed = *interation {
-            settings.grid_size = std::cmp::max(8, settings.grid_size.saturating_sub(1));
+            let size = grid_dimension(&mut settings, button.0);
+            *size = std::cmp::max(8, size.saturating_sub(1));
         }
     }
 }
 
 fn grid_increase(
-    query: Query<&Interaction, (Changed<Interaction>, With<GridIncreaseButton>)>,
+    query: Query<(&Interaction, &GridIncreaseButton), Changed<Interaction>>,
     mut settings: ResMut<SimulationSettings>,
 ) {
-    for interation in query {
+    for (interation, button) in query {
         if let Interaction::Pressed = *interation {
-            settings.grid_size = std::cmp::min(64, settings.grid_size.saturating_add(1));
+            let size = grid_dimension(&mut settings, button.0);
+            *size = std::cmp::min(64, size.saturating_add(1));
         }
     }
 }
 
 fn grid_text_update(
-    mut query: Query<&mut Text, With<GridTextLabel>>,
+    mut query: Query<(&mut Text, &GridTextLabel)>,
     settings: Res<SimulationSettings>,
 ) {
-    for mut text in &mut query {
-        text.0 = format!("Map size: {:<2}", settings.grid_size);
+    for (mut text, label) in &mut query {
+        text.0 = match label.0 {
+            GridDimension::Width => format!("Map width: {:<2}", settings.grid_width),
+            GridDimension::Height => format!("Map height: {:<2}", settings.grid_height),
+        };
+    }
+}
+
+fn grid_dimension(settings: &mut SimulationSettings, dimension: GridDimension) -> &mut usize {
+    match dimension {
+        GridDimension::Width => &mut settings.grid_width,
+        GridDimension::Height => &mut settings.grid_height,
     }
 }
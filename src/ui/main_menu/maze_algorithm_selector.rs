@@ -0,0 +1,15 @@
+use crate::settings::{MazeAlgorithm, SimulationSettings};
+
+use super::selector::MenuSetting;
+
+impl MenuSetting for MazeAlgorithm {
+    const LABEL: &'static str = "Maze";
+
+    fn get(settings: &SimulationSettings) -> Self {
+        settings.maze_algorithm
+    }
+
+    fn set(self, settings: &mut SimulationSettings) {
+        settings.maze_algorithm = self;
+    }
+}
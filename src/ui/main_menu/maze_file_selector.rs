@@ -0,0 +1,172 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{GameState, maze::Maze, settings::SimulationSettings};
+
+use super::{MainMenuItem, spawn_menu};
+
+const MAZES_DIR: &str = "mazes";
+
+#[derive(Component)]
+struct MazeFilePrevButton;
+
+#[derive(Component)]
+struct MazeFileNextButton;
+
+#[derive(Component)]
+struct MazeFileTextLabel;
+
+pub fn maze_file_selector_bundle() -> impl Bundle + use<> {
+    (
+        MainMenuItem,
+        Node {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            column_gap: Val::Px(20.0),
+            ..default()
+        },
+        children![
+            (
+                MainMenuItem,
+                MazeFilePrevButton,
+                Button,
+                BorderRadius::MAX,
+                BorderColor(Color::BLACK),
+                Node {
+                    width: Val::Px(45.0),
+                    height: Val::Px(45.0),
+                    border: UiRect::all(Val::Px(5.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![MainMenuItem, Text::new("<"), TextColor(Color::WHITE)]
+            ),
+            (
+                MainMenuItem,
+                Node {
+                    width: Val::Px(360.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(MazeFileTextLabel, Text::default(), TextColor(Color::WHITE))]
+            ),
+            (
+                MainMenuItem,
+                MazeFileNextButton,
+                Button,
+                BorderRadius::MAX,
+                BorderColor(Color::BLACK),
+                Node {
+                    width: Val::Px(45.0),
+                    height: Val::Px(45.0),
+                    border: UiRect::all(Val::Px(5.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![MainMenuItem, Text::new(">"), TextColor(Color::WHITE)]
+            )
+        ],
+    )
+}
+
+pub struct MazeFileSelectorPlugin;
+
+impl Plugin for MazeFileSelectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InMenu),
+            maze_file_text_update.after(spawn_menu),
+        );
+        app.add_systems(
+            Update,
+            (maze_file_prev, maze_file_next).run_if(in_state(GameState::InMenu)),
+        );
+        app.add_systems(
+            Update,
+            maze_file_text_update
+                .run_if(in_state(GameState::InMenu).and(resource_changed::<SimulationSettings>)),
+        );
+    }
+}
+
+fn maze_file_prev(
+    query: Query<&Interaction, (Changed<Interaction>, With<MazeFilePrevButton>)>,
+    mut settings: ResMut<SimulationSettings>,
+) {
+    for interation in query {
+        if let Interaction::Pressed = *interation {
+            select_maze_file(&mut settings, false);
+        }
+    }
+}
+
+fn maze_file_next(
+    query: Query<&Interaction, (Changed<Interaction>, With<MazeFileNextButton>)>,
+    mut settings: ResMut<SimulationSettings>,
+) {
+    for interation in query {
+        if let Interaction::Pressed = *interation {
+            select_maze_file(&mut settings, true);
+        }
+    }
+}
+
+/// Selects the previous or next file that is a valid maze, the invalid ones are skipped.
+fn select_maze_file(settings: &mut SimulationSettings, forward: bool) {
+    let files = list_maze_files();
+    let step = if forward { 1 } else { files.len() - 1 };
+    let mut index = selected_index(&files, &settings.maze_file);
+    loop {
+        index = (index + step) % files.len();
+        // the generated maze is always in the list, so this ends
+        let Some(path) = &files[index] else {
+            break;
+        };
+        match Maze::load(path) {
+            Ok(_) => break,
+            Err(e) => eprintln!("Skipping invalid maze file '{}': {}", path, e),
+        }
+    }
+    settings.maze_file = files[index].clone();
+}
+
+fn maze_file_text_update(
+    mut query: Query<&mut Text, With<MazeFileTextLabel>>,
+    settings: Res<SimulationSettings>,
+) {
+    let name = settings.maze_file.as_ref().map_or("generated", |path| {
+        Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(path)
+    });
+    for mut text in &mut query {
+        text.0 = format!("Maze file: {}", name);
+    }
+}
+
+/// The `.txt` files in the mazes folder, after the option of generating the maze.
+fn list_maze_files() -> Vec<Option<String>> {
+    let mut files = fs::read_dir(MAZES_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .filter_map(|path| path.to_str().map(String::from))
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+    files.sort();
+    std::iter::once(None)
+        .chain(files.into_iter().map(Some))
+        .collect()
+}
+
+fn selected_index(files: &[Option<String>], selected: &Option<String>) -> usize {
+    files.iter().position(|file| file == selected).unwrap_or(0)
+}
@@ -1,22 +1,31 @@
 use agent_selector::{AgentSelectorPlugin, agent_selector_bundle};
 use bevy::prelude::*;
 use communication_options::{CommunicationOptionsPlugin, communication_options_bundle};
-use grid_size_selector::{GridSelectorPlugin, grid_selector_bundle};
+use grid_size_selector::{GridDimension, GridSelectorPlugin, grid_selector_bundle};
+use maze_file_selector::{MazeFileSelectorPlugin, maze_file_selector_bundle};
 use quit::{QuitButtonPlugin, quit_button_bundle};
 use seed_input::{SeedInputPlugin, seed_input_bundle};
-use speed_selector::{SpeedSelectorPlugin, speed_selector_bundle};
+use selector::{SelectorPlugin, selector_bundle};
 use start::{StartButtonPlugin, start_button_bundle};
 use title::title_bundle;
 
-use crate::GameState;
+use crate::{
+    GameState,
+    settings::{MazeAlgorithm, SimulationSpeed, SpawnPlacement, Strategy},
+};
 
 mod agent_selector;
 mod communication_options;
 mod grid_size_selector;
+mod maze_algorithm_selector;
+mod maze_file_selector;
 mod quit;
 mod seed_input;
+mod selector;
+mod spawn_placement_selector;
 mod speed_selector;
 mod start;
+mod strategy_selector;
 mod title;
 
 pub struct MainMenuPlugin;
@@ -26,7 +35,11 @@ impl Plugin for MainMenuPlugin {
         app.add_plugins((
             AgentSelectorPlugin,
             GridSelectorPlugin,
-            SpeedSelectorPlugin,
+            SelectorPlugin::<MazeAlgorithm>::default(),
+            MazeFileSelectorPlugin,
+            SelectorPlugin::<SpawnPlacement>::default(),
+            SelectorPlugin::<Strategy>::default(),
+            SelectorPlugin::<SimulationSpeed>::default(),
             SeedInputPlugin,
             CommunicationOptionsPlugin,
             StartButtonPlugin,
@@ -76,8 +89,13 @@ fn main_menu_bundle() -> impl Bundle + use<> {
                 },
                 children![
                     agent_selector_bundle(),
-                    grid_selector_bundle(),
-                    speed_selector_bundle(),
+                    grid_selector_bundle(GridDimension::Width),
+                    grid_selector_bundle(GridDimension::Height),
+                    selector_bundle::<MazeAlgorithm>(),
+                    maze_file_selector_bundle(),
+                    selector_bundle::<SpawnPlacement>(),
+                    selector_bundle::<Strategy>(),
+                    selector_bundle::<SimulationSpeed>(),
                     seed_input_bundle(),
                     communication_options_bundle()
                 ]
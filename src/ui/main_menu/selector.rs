@@ -0,0 +1,146 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{
+    GameState,
+    settings::{Selectable, SimulationSettings},
+};
+
+use super::{MainMenuItem, spawn_menu};
+
+/// A setting of the menu picked with the previous and next buttons of a selector.
+pub trait MenuSetting: Selectable + Send + Sync {
+    const LABEL: &'static str;
+    const WIDTH: f32 = 360.0;
+
+    fn get(settings: &SimulationSettings) -> Self;
+
+    fn set(self, settings: &mut SimulationSettings);
+}
+
+#[derive(Component)]
+struct PrevButton<T>(PhantomData<T>);
+
+#[derive(Component)]
+struct NextButton<T>(PhantomData<T>);
+
+#[derive(Component)]
+struct TextLabel<T>(PhantomData<T>);
+
+pub fn selector_bundle<T: MenuSetting>() -> impl Bundle + use<T> {
+    (
+        MainMenuItem,
+        Node {
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            column_gap: Val::Px(20.0),
+            ..default()
+        },
+        children![
+            (
+                MainMenuItem,
+                PrevButton::<T>(PhantomData),
+                Button,
+                BorderRadius::MAX,
+                BorderColor(Color::BLACK),
+                Node {
+                    width: Val::Px(45.0),
+                    height: Val::Px(45.0),
+                    border: UiRect::all(Val::Px(5.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![MainMenuItem, Text::new("<"), TextColor(Color::WHITE)]
+            ),
+            (
+                MainMenuItem,
+                Node {
+                    width: Val::Px(T::WIDTH),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![(
+                    TextLabel::<T>(PhantomData),
+                    Text::default(),
+                    TextColor(Color::WHITE)
+                )]
+            ),
+            (
+                MainMenuItem,
+                NextButton::<T>(PhantomData),
+                Button,
+                BorderRadius::MAX,
+                BorderColor(Color::BLACK),
+                Node {
+                    width: Val::Px(45.0),
+                    height: Val::Px(45.0),
+                    border: UiRect::all(Val::Px(5.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                children![MainMenuItem, Text::new(">"), TextColor(Color::WHITE)]
+            )
+        ],
+    )
+}
+
+pub struct SelectorPlugin<T>(PhantomData<T>);
+
+impl<T> Default for SelectorPlugin<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: MenuSetting> Plugin for SelectorPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InMenu),
+            text_update::<T>.after(spawn_menu),
+        );
+        app.add_systems(
+            Update,
+            (select_prev::<T>, select_next::<T>).run_if(in_state(GameState::InMenu)),
+        );
+        app.add_systems(
+            Update,
+            text_update::<T>
+                .run_if(in_state(GameState::InMenu).and(resource_changed::<SimulationSettings>)),
+        );
+    }
+}
+
+fn select_prev<T: MenuSetting>(
+    query: Query<&Interaction, (Changed<Interaction>, With<PrevButton<T>>)>,
+    mut settings: ResMut<SimulationSettings>,
+) {
+    for interation in query {
+        if let Interaction::Pressed = *interation {
+            T::get(&settings).prev().set(&mut settings);
+        }
+    }
+}
+
+fn select_next<T: MenuSetting>(
+    query: Query<&Interaction, (Changed<Interaction>, With<NextButton<T>>)>,
+    mut settings: ResMut<SimulationSettings>,
+) {
+    for interation in query {
+        if let Interaction::Pressed = *interation {
+            T::get(&settings).next().set(&mut settings);
+        }
+    }
+}
+
+fn text_update<T: MenuSetting>(
+    mut query: Query<&mut Text, With<TextLabel<T>>>,
+    settings: Res<SimulationSettings>,
+) {
+    for mut text in &mut query {
+        text.0 = format!("{}: {}", T::LABEL, T::get(&settings).as_str());
+    }
+}
@@ -0,0 +1,15 @@
+use crate::settings::{SimulationSettings, SpawnPlacement};
+
+use super::selector::MenuSetting;
+
+impl MenuSetting for SpawnPlacement {
+    const LABEL: &'static str = "Spawn";
+
+    fn get(settings: &SimulationSettings) -> Self {
+        settings.spawn_placement
+    }
+
+    fn set(self, settings: &mut SimulationSettings) {
+        settings.spawn_placement = self;
+    }
+}
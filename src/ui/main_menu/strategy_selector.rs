@@ -0,0 +1,15 @@
+use crate::settings::{SimulationSettings, Strategy};
+
+use super::selector::MenuSetting;
+
+impl MenuSetting for Strategy {
+    const LABEL: &'static str = "Strategy";
+
+    fn get(settings: &SimulationSettings) -> Self {
+        settings.strategy
+    }
+
+    fn set(self, settings: &mut SimulationSettings) {
+        settings.strategy = self;
+    }
+}
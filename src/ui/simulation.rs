@@ -1,11 +1,13 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, ui::RelativeCursorPosition};
 
 use crate::{
     GameState,
     agent::SharedMazeKnowledge,
+    controls::timeline_controller::{SimulationHistory, TimelineSeekEvent},
+    maze::Maze,
     rng::{GlobalRng, seed_rng},
-    settings::SimulationSettings,
-    simulation::{SimulationStepData, SimulationStepEvent},
+    settings::{Mission, SimulationSettings},
+    simulation::SimulationStepData,
 };
 
 pub struct SimulationUiPlugin;
@@ -16,13 +18,14 @@ impl Plugin for SimulationUiPlugin {
         app.add_systems(OnExit(GameState::Simulation), despawn_ui);
         app.add_systems(
             Update,
-            (update_remaining_agents.run_if(resource_changed::<SharedMazeKnowledge>),)
+            (
+                update_remaining_agents.run_if(resource_changed::<SharedMazeKnowledge>),
+                update_timesteps.run_if(resource_changed::<SimulationStepData>),
+                update_timeline_fill,
+                seek_timeline,
+            )
                 .run_if(in_state(GameState::Simulation)),
         );
-        app.add_systems(
-            FixedUpdate,
-            update_timesteps.run_if(on_event::<SimulationStepEvent>),
-        );
     }
 }
 
@@ -35,8 +38,15 @@ struct TimestepsText;
 #[derive(Component)]
 struct RemainingAgentsText;
 
+#[derive(Component)]
+struct TimelineBar;
+
+#[derive(Component)]
+struct TimelineFill;
+
 fn spawn_ui(mut commands: Commands, settings: Res<SimulationSettings>, rng: Res<GlobalRng>) {
     commands.spawn(simulation_ui_bundle(&settings, &rng));
+    commands.spawn(timeline_bundle());
 }
 
 fn despawn_ui(mut commands: Commands, query: Query<Entity, With<SimulationUiItem>>) {
@@ -57,12 +67,88 @@ fn update_timesteps(
 fn update_remaining_agents(
     mut query: Query<&mut Text, With<RemainingAgentsText>>,
     knowledge: Res<SharedMazeKnowledge>,
+    settings: Res<SimulationSettings>,
+    maze: Res<Maze>,
 ) {
     query.iter_mut().for_each(|mut text| {
-        text.0 = format!("Remaining agents: {:>2}", knowledge.remaining_agents)
+        text.0 = match settings.mission {
+            Mission::AnyExit => format!("Remaining agents: {:>2}", knowledge.remaining_agents),
+            Mission::CollectAll => format!(
+                "Visited goals: {}/{}",
+                knowledge.visited_goals.len(),
+                maze.goals.len()
+            ),
+        }
+    });
+}
+
+fn update_timeline_fill(
+    mut query: Query<&mut Node, With<TimelineFill>>,
+    history: Res<SimulationHistory>,
+    step: Res<SimulationStepData>,
+) {
+    let first = history.first_timestep();
+    let progress = match history.last_timestep() - first {
+        0 => 0.0,
+        length => step.timesteps.saturating_sub(first) as f32 / length as f32,
+    };
+    query.iter_mut().for_each(|mut node| {
+        node.width = Val::Percent(progress * 100.0);
     });
 }
 
+fn seek_timeline(
+    query: Query<(&Interaction, &RelativeCursorPosition), With<TimelineBar>>,
+    history: Res<SimulationHistory>,
+    step: Res<SimulationStepData>,
+    mut events: EventWriter<TimelineSeekEvent>,
+) {
+    for (interaction, cursor) in &query {
+        if let (Interaction::Pressed, Some(position)) = (*interaction, cursor.normalized) {
+            let progress = position.x.clamp(0.0, 1.0);
+            let first = history.first_timestep();
+            let length = history.last_timestep() - first;
+            let timestep = first + (progress * length as f32).round() as u64;
+            if timestep != step.timesteps {
+                events.write(TimelineSeekEvent(timestep));
+            }
+        }
+    }
+}
+
+fn timeline_bundle() -> impl Bundle + use<> {
+    (
+        SimulationUiItem,
+        Node {
+            width: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(20.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![(
+            TimelineBar,
+            Button,
+            RelativeCursorPosition::default(),
+            Node {
+                width: Val::Percent(60.0),
+                height: Val::Px(20.0),
+                ..default()
+            },
+            BackgroundColor(Color::linear_rgba(0.05, 0.05, 0.05, 0.8)),
+            children![(
+                TimelineFill,
+                Node {
+                    width: Val::Percent(0.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgba(0.6, 0.6, 0.6, 0.8)),
+            )]
+        )],
+    )
+}
+
 fn simulation_ui_bundle(
     settings: &Res<SimulationSettings>,
     rng: &Res<GlobalRng>,
@@ -106,7 +192,10 @@ fn simulation_ui_bundle(
                             TextColor(Color::WHITE)
                         ),
                         (
-                            Text::new(format!("Map size: {}", settings.grid_size)),
+                            Text::new(format!(
+                                "Map size: {}x{}",
+                                settings.grid_width, settings.grid_height
+                            )),
                             TextColor(Color::WHITE)
                         ),
                         (
@@ -0,0 +1,97 @@
+use pathfinding::{kuhn_munkres::kuhn_munkres, matrix::Matrix};
+
+/// Assigns each agent (row) at most one target (column) with the Hungarian
+/// method, as many agents as possible and then with the lowest total path
+/// length. Targets without a cost can not be reached by that agent.
+pub fn assign_targets(costs: &[Vec<Option<usize>>], targets: usize) -> Vec<Option<usize>> {
+    let agents = costs.len();
+    if agents == 0 || targets == 0 {
+        return vec![None; agents];
+    }
+    let max_cost = costs.iter().flatten().flatten().max().copied().unwrap_or(0);
+    // more than every path together, so leaving an agent out never pays off
+    let unassigned = ((max_cost + 1) * agents) as i64;
+    // the padding columns leave agents out when there are fewer targets
+    let weights = Matrix::from_fn(agents, targets.max(agents), |(agent, target)| {
+        let cost = costs[agent].get(target).copied().flatten();
+        -cost.map_or(unassigned, |cost| cost as i64)
+    });
+    let (_, assignments) = kuhn_munkres(&weights);
+    assignments
+        .iter()
+        .enumerate()
+        .map(|(agent, target)| {
+            costs[agent]
+                .get(*target)
+                .copied()
+                .flatten()
+                .map(|_| *target)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total_cost(costs: &[Vec<Option<usize>>], assignments: &[Option<usize>]) -> usize {
+        assignments
+            .iter()
+            .enumerate()
+            .filter_map(|(agent, target)| target.and_then(|target| costs[agent][target]))
+            .sum()
+    }
+
+    #[test]
+    fn nothing_to_assign() {
+        assert!(assign_targets(&[], 3).is_empty());
+        assert_eq!(assign_targets(&[vec![], vec![]], 0), [None, None]);
+    }
+
+    #[test]
+    fn lowest_total_cost() {
+        let costs = vec![vec![Some(1), Some(2)], vec![Some(1), Some(9)]];
+        assert_eq!(assign_targets(&costs, 2), [Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn more_agents_than_targets() {
+        let costs = vec![vec![Some(5)], vec![Some(2)], vec![Some(7)]];
+        assert_eq!(assign_targets(&costs, 1), [None, Some(0), None]);
+    }
+
+    #[test]
+    fn more_targets_than_agents() {
+        let costs = vec![vec![Some(4), Some(3), Some(8), Some(1)]];
+        assert_eq!(assign_targets(&costs, 4), [Some(3)]);
+    }
+
+    #[test]
+    fn unreachable_targets() {
+        let costs = vec![vec![None, Some(3)], vec![Some(2), None], vec![None, None]];
+        assert_eq!(assign_targets(&costs, 2), [Some(1), Some(0), None]);
+    }
+
+    #[test]
+    fn as_many_agents_as_possible_before_the_cost() {
+        // agent 0 is closer to target 0, but only agent 1 can reach it
+        let costs = vec![vec![Some(1), Some(100)], vec![Some(2), None]];
+        assert_eq!(assign_targets(&costs, 2), [Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn ties_assign_distinct_targets() {
+        let costs = vec![vec![Some(3); 3]; 3];
+        let assignments = assign_targets(&costs, 3);
+        let mut targets = assignments
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<usize>>();
+        targets.sort();
+        assert_eq!(targets, [0, 1, 2]);
+        assert_eq!(total_cost(&costs, &assignments), 9);
+        // the same costs always give the same assignments
+        assert_eq!(assign_targets(&costs, 3), assignments);
+    }
+}
@@ -0,0 +1,202 @@
+use std::ops::{Index, IndexMut};
+
+use crate::maze::Tile;
+
+/// Values for every tile of a maze, the tiles themselves by default, stored row
+/// after row in a single vector and indexed by `(x, y)`.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct TileGrid<T = Tile> {
+    width: usize,
+    height: usize,
+    tiles: Vec<T>,
+}
+
+impl TileGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::filled(width, height, Tile::Wall)
+    }
+
+    /// Builds a grid from its rows, the shorter ones are filled up with walls.
+    pub fn from_rows(rows: Vec<Vec<Tile>>) -> Self {
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut grid = Self::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            grid.tiles[y * width..y * width + row.len()].copy_from_slice(row);
+        }
+        grid
+    }
+}
+
+impl<T: Copy> TileGrid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![value; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The tile at the position, `None` outside of the grid.
+    pub fn get(&self, (x, y): (usize, usize)) -> Option<T> {
+        if x < self.width && y < self.height {
+            Some(self.tiles[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.tiles.chunks(self.width.max(1))
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(i, tile)| ((i % self.width, i / self.width), *tile))
+    }
+}
+
+impl<T> Index<(usize, usize)> for TileGrid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        &self.tiles[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for TileGrid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        &mut self.tiles[y * self.width + x]
+    }
+}
+
+/// A set of tile positions of a grid, one bit per tile.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct TileSet {
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+impl TileSet {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            bits: vec![0; (width * height).div_ceil(64)],
+        }
+    }
+
+    fn bit(&self, (x, y): (usize, usize)) -> Option<(usize, u64)> {
+        if x < self.width && y < self.height {
+            let i = y * self.width + x;
+            Some((i / 64, 1 << (i % 64)))
+        } else {
+            None
+        }
+    }
+
+    /// Adds the position and returns whether it was not in the set yet.
+    pub fn insert(&mut self, position: (usize, usize)) -> bool {
+        let (word, mask) = self
+            .bit(position)
+            .expect("Position outside of the tile set");
+        let inserted = self.bits[word] & mask == 0;
+        self.bits[word] |= mask;
+        inserted
+    }
+
+    pub fn contains(&self, position: &(usize, usize)) -> bool {
+        self.bit(*position)
+            .is_some_and(|(word, mask)| self.bits[word] & mask != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The memory used by the bits of the set.
+    pub fn byte_size(&self) -> usize {
+        self.bits.len() * size_of::<u64>()
+    }
+
+    pub fn union(&self, other: &TileSet) -> TileSet {
+        let mut union = self.clone();
+        union.union_with(other);
+        union
+    }
+
+    pub fn union_with(&mut self, other: &TileSet) {
+        if self.bits.is_empty() {
+            *self = other.clone();
+            return;
+        }
+        self.bits
+            .iter_mut()
+            .zip(&other.bits)
+            .for_each(|(word, other_word)| *word |= other_word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_len() {
+        let mut set = TileSet::new(10, 10);
+        assert!(set.insert((0, 0)));
+        assert!(set.insert((9, 9)));
+        // across the boundary between two words
+        assert!(set.insert((3, 6)));
+        assert!(set.insert((4, 6)));
+        assert!(!set.insert((9, 9)));
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(&(3, 6)));
+        assert!(!set.contains(&(5, 6)));
+        assert!(!set.contains(&(10, 0)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_outside() {
+        TileSet::new(4, 4).insert((4, 0));
+    }
+
+    #[test]
+    fn union() {
+        let mut a = TileSet::new(8, 8);
+        let mut b = TileSet::new(8, 8);
+        a.insert((1, 1));
+        a.insert((2, 2));
+        b.insert((2, 2));
+        b.insert((7, 7));
+        let union = a.union(&b);
+        assert_eq!(union.len(), 3);
+        assert!([(1, 1), (2, 2), (7, 7)].iter().all(|p| union.contains(p)));
+        assert_eq!(a.len(), 2);
+    }
+
+    #[test]
+    fn union_with_empty_set() {
+        let mut a = TileSet::default();
+        let mut b = TileSet::new(8, 8);
+        b.insert((5, 3));
+        a.union_with(&b);
+        assert_eq!(a, b);
+        b.union_with(&TileSet::new(8, 8));
+        assert_eq!(b.len(), 1);
+    }
+}
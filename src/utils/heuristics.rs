@@ -1,4 +1,6 @@
-use std::{cmp::Ordering, collections::HashSet};
+use std::cmp::Ordering;
+
+use super::grid::TileSet;
 
 pub fn manhattan(a: (usize, usize), b: (usize, usize)) -> i32 {
     (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as i32
@@ -10,11 +12,7 @@ pub fn manhattan_cmp(n1: (usize, usize), n2: (usize, usize), goal: (usize, usize
     n2_dist.cmp(&n1_dist)
 }
 
-pub fn explored_cmp(
-    n1: (usize, usize),
-    n2: (usize, usize),
-    explored: &HashSet<(usize, usize)>,
-) -> Ordering {
+pub fn explored_cmp(n1: (usize, usize), n2: (usize, usize), explored: &TileSet) -> Ordering {
     if explored.contains(&n1) && !explored.contains(&n2) {
         Ordering::Less
     } else if !explored.contains(&n1) && explored.contains(&n2) {
@@ -34,9 +32,9 @@ pub fn neighbors_cmp(
     n2_dist.cmp(&n1_dist)
 }
 
-pub fn border_cmp(n1: (usize, usize), n2: (usize, usize), map_size: usize) -> Ordering {
-    let n1_dist = border_dist(n1, map_size);
-    let n2_dist = border_dist(n2, map_size);
+pub fn border_cmp(n1: (usize, usize), n2: (usize, usize), width: usize, height: usize) -> Ordering {
+    let n1_dist = border_dist(n1, width, height);
+    let n2_dist = border_dist(n2, width, height);
     n2_dist.cmp(&n1_dist)
 }
 
@@ -50,6 +48,10 @@ pub fn goal_cmp(n1: (usize, usize), n2: (usize, usize), goal: (usize, usize)) ->
     }
 }
 
+pub fn goals_cmp(n1: (usize, usize), n2: (usize, usize), goals: &[(usize, usize)]) -> Ordering {
+    goals.contains(&n1).cmp(&goals.contains(&n2))
+}
+
 fn min_neighbor_distance(neighbors: &Vec<(usize, usize)>, position: (usize, usize)) -> i32 {
     neighbors
         .iter()
@@ -58,9 +60,6 @@ fn min_neighbor_distance(neighbors: &Vec<(usize, usize)>, position: (usize, usiz
         .unwrap_or(i32::MAX)
 }
 
-fn border_dist(node: (usize, usize), map_size: usize) -> usize {
-    node.0
-        .min(node.1)
-        .min(map_size - node.0)
-        .min(map_size - node.1)
+fn border_dist(node: (usize, usize), width: usize, height: usize) -> usize {
+    node.0.min(node.1).min(width - node.0).min(height - node.1)
 }
@@ -1,2 +1,5 @@
+pub mod allocation;
+pub mod grid;
 pub mod heuristics;
 pub mod node_utils;
+pub mod spawn_utils;
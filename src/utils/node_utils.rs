@@ -1,37 +1,137 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::maze::{Maze, Tile};
+use crate::{
+    maze::{Maze, Tile},
+    settings::Sensor,
+};
+
+use super::grid::TileSet;
 
 use super::heuristics;
 
-fn calculate_neighbors(node: (usize, usize), max_size: usize) -> [(usize, usize); 4] {
+fn calculate_neighbors(node: (usize, usize), width: usize, height: usize) -> [(usize, usize); 4] {
     let mut neighbors = [(0, 0); 4];
     neighbors[0] = (node.0.saturating_sub(1), node.1);
-    neighbors[1] = (
-        std::cmp::min(node.0.saturating_add(1), max_size - 1),
-        node.1,
-    );
+    neighbors[1] = (std::cmp::min(node.0.saturating_add(1), width - 1), node.1);
     neighbors[2] = (node.0, node.1.saturating_sub(1));
-    neighbors[3] = (
-        node.0,
-        std::cmp::min(node.1.saturating_add(1), max_size - 1),
-    );
+    neighbors[3] = (node.0, std::cmp::min(node.1.saturating_add(1), height - 1));
     neighbors
 }
 
+/// What an agent learns by sensing around a tile.
+pub struct Observation {
+    /// Open tiles with every neighbor seen, nothing is left to explore there
+    pub explored: Vec<(usize, usize)>,
+    /// New open tiles that could still lead somewhere unseen
+    pub frontier: Vec<(usize, usize)>,
+    /// Every tile in view, walls and tiles that can not be walked to yet included
+    pub seen: Vec<(usize, usize)>,
+}
+
 pub fn explore_node(
     node: (usize, usize),
     maze: &Maze,
-    explored: &HashSet<(usize, usize)>,
+    sensor: &Sensor,
+    explored: &TileSet,
     frontier: &Vec<(usize, usize)>,
-) -> Vec<(usize, usize)> {
-    calculate_neighbors(node, maze.tile_grid.len())
+) -> Observation {
+    let sensed = sense(node, maze, sensor);
+    let seen = sensed.iter().collect::<HashSet<&(usize, usize)>>();
+    let fully_observed = |n: (usize, usize)| {
+        calculate_neighbors(n, maze.width(), maze.height())
+            .iter()
+            .all(|neighbor| *neighbor == node || seen.contains(neighbor))
+    };
+    // paths only go over explored tiles, so a tile seen behind a wall or past
+    // another frontier tile can not be walked to yet
+    let mut reachable = HashSet::from([node]);
+    let mut queue = VecDeque::from([node]);
+    while let Some(current) = queue.pop_front() {
+        for neighbor in calculate_neighbors(current, maze.width(), maze.height()) {
+            if seen.contains(&neighbor)
+                && maze.tile_grid[neighbor] != Tile::Wall
+                && reachable.insert(neighbor)
+                && (explored.contains(&neighbor) || fully_observed(neighbor))
+            {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    let (observed, children) = sensed
         .iter()
-        .filter(|n| !explored.contains(*n))
-        .filter(|n| !frontier.contains(*n))
         .filter(|n| **n != node)
-        .filter(|n| maze.tile_grid[n.1][n.0] != Tile::Wall)
-        .map(|n| *n)
+        .filter(|n| reachable.contains(*n))
+        .filter(|n| !explored.contains(n))
+        .partition::<Vec<(usize, usize)>, _>(|n| fully_observed(**n));
+    Observation {
+        explored: observed,
+        frontier: children
+            .into_iter()
+            .filter(|n| !frontier.contains(n))
+            .collect(),
+        seen: sensed,
+    }
+}
+
+/// The tiles seen from the node by the sensor, walls included, the closest ones first.
+pub fn sense(node: (usize, usize), maze: &Maze, sensor: &Sensor) -> Vec<(usize, usize)> {
+    let radius = sensor.radius as isize;
+    let tile = |(dx, dy): (isize, isize)| {
+        let position = (
+            node.0.checked_add_signed(dx)?,
+            node.1.checked_add_signed(dy)?,
+        );
+        maze.tile_grid.get(position)
+    };
+    let mut offsets = Vec::new();
+    if sensor.line_of_sight {
+        let mut directions = vec![(-1, 0), (1, 0), (0, -1), (0, 1)];
+        if sensor.diagonal {
+            directions.extend([(-1, -1), (1, -1), (-1, 1), (1, 1)]);
+        }
+        for (dx, dy) in directions {
+            for k in 1..=radius {
+                let (x, y) = (dx * k, dy * k);
+                let Some(current) = tile((x, y)) else {
+                    break;
+                };
+                // diagonally it can not see between two walls touching at a corner
+                let squeezed =
+                    tile((x - dx, y)) == Some(Tile::Wall) && tile((x, y - dy)) == Some(Tile::Wall);
+                if dx != 0 && dy != 0 && squeezed {
+                    break;
+                }
+                offsets.push((x, y));
+                if current == Tile::Wall {
+                    break;
+                }
+                // looking through a tile also shows what is next to it
+                if k < radius {
+                    offsets.extend([(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]);
+                }
+            }
+        }
+    } else {
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                let distance = if sensor.diagonal {
+                    x.abs().max(y.abs())
+                } else {
+                    x.abs() + y.abs()
+                };
+                if distance <= radius {
+                    offsets.push((x, y));
+                }
+            }
+        }
+    }
+    // the same order as the neighbors of a tile for the closest ones
+    offsets.sort_by_key(|(x, y)| (x.abs() + y.abs(), y.abs(), *y, *x));
+    offsets.dedup();
+    offsets
+        .into_iter()
+        .filter(|offset| *offset != (0, 0) && tile(*offset).is_some())
+        .map(|(x, y)| (node.0.wrapping_add_signed(x), node.1.wrapping_add_signed(y)))
         .collect()
 }
 
@@ -39,10 +139,10 @@ pub fn find_know_path_to_node(
     current: (usize, usize),
     dest: (usize, usize),
     maze: &Maze,
-    explored: &HashSet<(usize, usize)>,
+    explored: &TileSet,
 ) -> Option<Vec<(usize, usize)>> {
     let succ = |node: &(usize, usize)| {
-        calculate_neighbors(*node, maze.tile_grid.len())
+        calculate_neighbors(*node, maze.width(), maze.height())
             .iter()
             .filter(|n| explored.contains(n) || **n == dest)
             .map(|n| (*n, 1))
@@ -58,3 +158,27 @@ pub fn find_know_path_to_node(
             .collect::<Vec<(usize, usize)>>()
     })
 }
+
+/// The length of the shortest known path, like [`find_know_path_to_node`], to
+/// every explored tile and every open tile next to them.
+pub fn known_path_lengths(
+    current: (usize, usize),
+    maze: &Maze,
+    explored: &TileSet,
+) -> HashMap<(usize, usize), usize> {
+    let mut lengths = HashMap::from([(current, 0)]);
+    let mut queue = VecDeque::from([current]);
+    while let Some(node) = queue.pop_front() {
+        if node != current && !explored.contains(&node) {
+            continue;
+        }
+        let length = lengths[&node] + 1;
+        for n in calculate_neighbors(node, maze.width(), maze.height()) {
+            if maze.tile_grid[n] != Tile::Wall && !lengths.contains_key(&n) {
+                lengths.insert(n, length);
+                queue.push_back(n);
+            }
+        }
+    }
+    lengths
+}
@@ -0,0 +1,72 @@
+use std::cmp::Reverse;
+
+use rand::{rngs::StdRng, seq::SliceRandom};
+
+use crate::{
+    analysis,
+    maze::Maze,
+    settings::{SimulationSettings, SpawnPlacement},
+};
+
+use super::heuristics;
+
+/// Picks the spawn positions of the agents among the floor tiles of the maze.
+pub fn spawn_positions(
+    maze: &Maze,
+    mut floor: Vec<(usize, usize)>,
+    settings: &SimulationSettings,
+    rng: &mut StdRng,
+) -> Vec<(usize, usize)> {
+    let count = settings.num_agents as usize;
+    floor.shuffle(rng);
+    match settings.spawn_placement {
+        SpawnPlacement::Random => {
+            // agents take the start positions marked in the maze first
+            floor.sort_by_key(|position| !maze.starts.contains(position));
+        }
+        SpawnPlacement::Cluster => {
+            if let Some(center) = floor.first().copied() {
+                let distances = analysis::path_distances(maze, [center]);
+                floor.sort_by_key(|position| distances[*position].unwrap_or(usize::MAX));
+            }
+        }
+        // a maze file without start markers falls back to random positions
+        SpawnPlacement::Start if !maze.starts.is_empty() => {
+            return maze.starts.iter().cycle().take(count).copied().collect();
+        }
+        SpawnPlacement::Start => {}
+        SpawnPlacement::Spread => return spread_positions(&floor, count),
+        SpawnPlacement::GoalDistance => {
+            let distances = analysis::goal_distances(maze);
+            let min_distance = settings.spawn_goal_distance;
+            // the farthest tiles fill in when not enough of them are far enough
+            floor.sort_by_key(|position| {
+                Reverse(distances[*position].unwrap_or(0).min(min_distance))
+            });
+        }
+    }
+    floor.into_iter().take(count).collect()
+}
+
+/// Starts from a random tile and keeps taking the tile farthest away from the taken ones.
+fn spread_positions(floor: &[(usize, usize)], count: usize) -> Vec<(usize, usize)> {
+    let mut positions = Vec::with_capacity(count.min(floor.len()));
+    // distance from every floor tile to the closest taken position
+    let mut min_distance = vec![i32::MAX; floor.len()];
+    let mut next = 0;
+    while positions.len() < count.min(floor.len()) {
+        let taken = floor[next];
+        positions.push(taken);
+        floor
+            .iter()
+            .zip(&mut min_distance)
+            .for_each(|(tile, distance)| {
+                *distance = (*distance).min(heuristics::manhattan(*tile, taken));
+            });
+        match (0..floor.len()).max_by_key(|i| min_distance[*i]) {
+            Some(farthest) => next = farthest,
+            None => break,
+        }
+    }
+    positions
+}